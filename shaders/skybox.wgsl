@@ -0,0 +1,33 @@
+struct SkyboxUniform {
+    inv_view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) clip: vec2<f32>,
+};
+
+// Fullscreen triangle on the far plane, so only pixels no geometry was drawn to pass the depth test.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(out.clip, 1.0, 1.0);
+    return out;
+}
+
+@group(1) @binding(0)
+var t_skybox: texture_cube<f32>;
+@group(1) @binding(1)
+var s_skybox: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = skybox.inv_view_proj * vec4<f32>(in.clip, 1.0, 1.0);
+    let direction = normalize(world.xyz / world.w);
+    return textureSample(t_skybox, s_skybox, direction);
+}
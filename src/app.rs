@@ -2,8 +2,8 @@ use std::sync::Arc;
 
 use winit::event_loop::ControlFlow;
 
-use crate::{window::{Window, Events}, engine::{context::{Context, Surface}, renderer::Renderer, input::{InputState, Key}, gpu_resource::GpuResource}, asset::{texture::Texture, handle::Handle, primitives::PrimitiveMesh, asset_manager::{AssetManager, self}, mesh::Mesh, self}, objects::camera::{Camera, Projection, CameraController}, util::cast_slice};
-use bevy_ecs::{world::World, schedule::Schedule};
+use crate::{window::{Window, Events}, engine::{context::{Context, Surface}, renderer::Renderer, input::{InputState, Key}, gpu_resource::GpuResource}, asset::{texture::Texture, handle::Handle, primitives::PrimitiveMesh, asset_manager::{AssetManager, self}, mesh::{Mesh, MeshSettings}, self}, objects::camera::{Camera, Projection, CameraController}, util::cast_slice};
+use bevy_ecs::{world::World, schedule::Schedule, entity::Entity};
 use crate::component;
 
 pub struct App {
@@ -26,7 +26,10 @@ impl App {
         let renderer = Renderer::new(&context.device, &surface.config, &surface.extent);
         let mut asset_manager = AssetManager::new(context.clone());
         
-        let texture = asset_manager.get_handle::<Texture>("res/textures/stone_bricks.jpg");
+        let texture = asset_manager.get_named::<Texture>("stone_bricks").unwrap_or_else(|| {
+            log::error!("stone_bricks is missing from assets.yaml");
+            asset_manager.get_default_handle()
+        });
         let mesh = asset_manager.get_primitive_handle(PrimitiveMesh::Quad);
         let transform = component::Transform::new(glam::Vec3::ZERO, glam::Vec3::ZERO, 1.0);
 
@@ -59,6 +62,11 @@ impl App {
         }
     }
 
+    /// Imports a glTF scene and spawns its nodes as entities in the app's `World`.
+    pub async fn load_scene(&mut self, file_path: &str) -> anyhow::Result<Vec<Entity>> {
+        asset::loaders::gltf::spawn_scene(&self.context, &mut self.asset_manager, &mut self.world, file_path, &MeshSettings::default()).await
+    }
+
     pub fn resize(&mut self, new_size: [u32; 2]) {
         if new_size[0] > 0 && new_size[1] > 0 {
             self.surface.config.width = new_size[0];
@@ -76,10 +84,13 @@ impl App {
         self.context.queue.write_buffer(&self.camera.buffer, 0, cast_slice(&[self.camera.uniform]));
 
         self.asset_manager.process_pending();
+        self.asset_manager.send_events(&mut self.world);
+
+        Renderer::select_lods(&mut self.world, &self.camera, &self.asset_manager);
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        self.renderer.draw(&self.context, &self.surface, &self.camera, &self.entity_transform, &self.entity, &self.asset_manager)
+        self.renderer.draw(&self.context, &self.surface, &self.camera, &self.entity_transform, &self.entity, &self.world, &self.asset_manager)
     }
 }
 
@@ -1,121 +1,753 @@
-use std::{sync::{Arc, mpsc::{self, Receiver}}, collections::HashMap, any::TypeId};
+use std::{panic::AssertUnwindSafe, sync::{Arc, Mutex, Weak, mpsc::{self, Receiver, TryRecvError}}, collections::{HashMap, HashSet}, any::{Any, TypeId}, path::Path};
 
+use anyhow::{anyhow, Context as _, Result};
 use futures::FutureExt;
 
 use crate::engine::context::Context;
 
-use super::{pools::AssetPool, mesh::Mesh, texture::Texture, handle::Handle, Asset, primitives::PrimitiveMesh};
+use super::{pools::{AssetPool, AnyPool}, mesh::Mesh, texture::{Texture, TextureSettings}, cube_texture::CubeTexture, atlas::{AtlasBuilder, AtlasRegion, TextureAtlas}, handle::{AssetId, Handle}, events::{self, AssetChange}, load_group::{LoadGroup, LoadGroupState}, loader::{AssetLoader, LoadPriority}, Asset, primitives::{Primitive, PrimitiveMesh}, watcher::AssetWatcher, manifest::{Manifest, ManifestEntry}, meta, cache::AssetCache, sources::{AssetSource, AssetSources}};
 use bevy_ecs::prelude::*;
 
-enum AssetType {
-    Texture(Arc<Texture>),
-    Mesh(Arc<Mesh>),
+/// A loaded `Arc<T>`, boxed so results of every asset type can share the pending channels.
+type LoadResult = Result<Box<dyn Any + Send + Sync>>;
+
+pub const MANIFEST_PATH: &str = "assets.yaml";
+
+#[derive(Clone, Debug)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(Arc<anyhow::Error>),
+}
+
+struct AssetRegistration {
+    pool: Box<dyn AnyPool>,
+    reload: fn(&mut AssetManager, AssetId, String),
+    from_manifest: fn(&mut AssetManager, &str, serde_yaml::Value) -> Result<Box<dyn Any + Send + Sync>>,
+    send_events: fn(&mut World, Vec<(AssetId, AssetChange)>),
 }
 
 #[derive(Resource, Default)]
 pub struct AssetManager {
     context: Arc<Context>,
+    sources: Arc<AssetSources>,
+    cache: Option<Arc<AssetCache>>,
 
-    meshes: AssetPool<Mesh>,
-    textures: AssetPool<Texture>,
-    paths: HashMap<String, usize>,
+    registry: HashMap<TypeId, AssetRegistration>,
+    type_names: HashMap<&'static str, TypeId>,
+    paths: HashMap<String, AssetId>,
+    known_paths: HashMap<AssetId, String>,
+    load_states: HashMap<AssetId, LoadState>,
+    asset_types: HashMap<AssetId, TypeId>,
+    settings: HashMap<AssetId, Box<dyn Any + Send + Sync>>,
+    /// Priority each asset's loads are queued at, reused when it's reloaded.
+    priorities: HashMap<AssetId, LoadPriority>,
+    handles: HashMap<AssetId, Weak<()>>,
+    named: HashMap<String, Box<dyn Any + Send + Sync>>,
+    unresolved: Vec<(String, ManifestEntry)>,
+    atlas_regions: HashMap<(AssetId, String), AssetId>,
 
-    pending: Vec<Receiver<(usize, AssetType)>>,
+    loader: AssetLoader,
+    pending: Vec<(AssetId, Mutex<Receiver<LoadResult>>)>,
+    reloading: HashSet<AssetId>,
+    /// Assets that changed again while they were reloading, reloaded once more when that finishes.
+    stale: HashSet<AssetId>,
+    watcher: Option<AssetWatcher>,
 
-    next_id: usize,
+    changes: HashMap<TypeId, Vec<(AssetId, AssetChange)>>,
+    load_groups: Vec<Weak<Mutex<LoadGroupState>>>,
 }
 
 impl AssetManager {
     pub fn new(context: Arc<Context>) -> Self {
         let meshes = AssetPool::<Mesh>::new(&context);
         let textures = AssetPool::<Texture>::new(&context);
+        let cube_textures = AssetPool::<CubeTexture>::new(&context);
+        let atlases = AssetPool::with_default(Arc::new(TextureAtlas::default()));
+        let atlas_regions = AssetPool::with_default(Arc::new(AtlasRegion::whole(textures.get_default())));
 
-        let next_id = meshes.len() + textures.len();
-        
-        Self {
+        let mut asset_manager = Self {
             context,
+            sources: Arc::new(AssetSources::default()),
+            cache: None,
 
-            meshes,
-            textures,
+            registry: HashMap::new(),
+            type_names: HashMap::new(),
             paths: HashMap::new(),
-            
+            known_paths: HashMap::new(),
+            load_states: HashMap::new(),
+            asset_types: HashMap::new(),
+            settings: HashMap::new(),
+            priorities: HashMap::new(),
+            handles: HashMap::new(),
+            named: HashMap::new(),
+            unresolved: Vec::new(),
+            atlas_regions: HashMap::new(),
+
+            loader: AssetLoader::default(),
             pending: Vec::new(),
+            reloading: HashSet::new(),
+            stale: HashSet::new(),
+            watcher: None,
+
+            changes: HashMap::new(),
+            load_groups: Vec::new(),
+        };
+
+        asset_manager.register_pool(meshes);
+        asset_manager.register_pool(textures);
+        asset_manager.register_pool(cube_textures);
+        asset_manager.register_pool(atlases);
+        asset_manager.register_pool(atlas_regions);
+
+        if Path::new(MANIFEST_PATH).exists() {
+            if let Err(e) = asset_manager.load_manifest(MANIFEST_PATH) {
+                log::error!("Failed to load asset manifest: {:#}", e);
+            }
+        }
+
+        asset_manager
+    }
 
-            next_id,
+    /// Adds a new asset type, using `default` while its assets load and after a failed load.
+    /// Manifest entries of this type that were waiting on the registration are requested now.
+    pub fn register<T: Asset>(&mut self, default: Arc<T>) {
+        self.register_pool(AssetPool::<T>::with_default(default));
+    }
+
+    fn register_pool<T: Asset>(&mut self, pool: AssetPool<T>) {
+        if self.registry.contains_key(&TypeId::of::<T>()) {
+            log::warn!("Asset type already registered: {}", T::TYPE_NAME);
+            return;
+        }
+
+        self.type_names.insert(T::TYPE_NAME, TypeId::of::<T>());
+        self.registry.insert(TypeId::of::<T>(), AssetRegistration {
+            pool: Box::new(pool),
+            reload: Self::reload::<T>,
+            from_manifest: Self::get_manifest_handle::<T>,
+            send_events: events::send::<T>,
+        });
+
+        let (resolved, unresolved) = std::mem::take(&mut self.unresolved).into_iter()
+            .partition(|(_, entry)| entry.asset_type == T::TYPE_NAME);
+        self.unresolved = unresolved;
+
+        for (name, entry) in resolved {
+            if let Err(e) = self.load_manifest_entry(name, entry) {
+                log::error!("Failed to load asset manifest entry: {:#}", e);
+            }
         }
     }
 
-    pub fn get_handle<T: Asset + 'static>(&mut self, file_path: &str) -> Handle<T> {
+    /// Makes `source` available to asset paths prefixed with `name://`. Loads already in flight
+    /// keep using the sources they started with.
+    pub fn add_source(&mut self, name: &str, source: impl AssetSource + 'static) {
+        let mut sources = (*self.sources).clone();
+        sources.insert(name, Arc::new(source));
+        self.sources = Arc::new(sources);
+    }
+
+    /// The sources asset paths are currently read from.
+    pub fn sources(&self) -> Arc<AssetSources> {
+        Arc::clone(&self.sources)
+    }
+
+    /// Sets the cache cooked assets are loaded from, e.g. one in `cache::DEFAULT_CACHE_DIR`.
+    /// Defaults to `None`, which always loads from source.
+    pub fn set_cache(&mut self, cache: Option<AssetCache>) {
+        self.cache = cache.map(Arc::new);
+    }
+
+    /// Replaces the loader running new loads. Loads already queued still finish on the
+    /// previous loader's workers.
+    pub fn set_loader(&mut self, loader: AssetLoader) {
+        self.loader = loader;
+    }
+
+    /// Moves the asset's load ahead of or behind other queued loads, along with any later
+    /// reloads of it. A load that has already started isn't affected.
+    pub fn set_priority<T: Asset>(&mut self, handle: &Handle<T>, priority: LoadPriority) {
+        if let Some(current) = self.priorities.get_mut(&handle.asset_id) {
+            *current = priority;
+            self.loader.set_priority(handle.asset_id, priority);
+        }
+    }
+
+    pub fn get_handle<T: Asset>(&mut self, file_path: &str) -> Handle<T> {
+        self.get_handle_with_settings::<T>(file_path, T::Settings::default())
+    }
+
+    /// Settings only apply to the first request for a path, later requests share the loaded asset.
+    /// A `.meta` sidecar next to the asset overrides them, see `meta::meta_path`.
+    pub fn get_handle_with_settings<T: Asset>(&mut self, file_path: &str, settings: T::Settings) -> Handle<T> {
+        self.get_handle_with_priority::<T>(file_path, settings, LoadPriority::default())
+    }
+
+    /// Like `get_handle_with_settings`, but queues the load at `priority`. Hot reloads and
+    /// reloads of evicted assets keep that priority. Requesting an asset again at a higher
+    /// priority raises it, see `set_priority`.
+    pub fn get_handle_with_priority<T: Asset>(&mut self, file_path: &str, settings: T::Settings, priority: LoadPriority) -> Handle<T> {
         let file_path = file_path.to_owned();
 
+        // The last handle was dropped since the last `process_pending`, so its load may have
+        // been cancelled. Start over rather than revive it.
         if let Some(&asset_id) = self.paths.get(&file_path) {
-            Handle::<T>::new(asset_id)
+            if self.handles.get(&asset_id).and_then(Weak::upgrade).is_none() {
+                self.unload(asset_id);
+            }
+        }
+
+        if let Some(&asset_id) = self.paths.get(&file_path) {
+            let handle = self.strong_handle(asset_id);
+            if self.priorities.get(&asset_id).is_some_and(|&current| priority > current) {
+                self.set_priority(&handle, priority);
+            }
+            handle
         } else {
-            let asset_id = self.get_new_id();
+            if !self.registry.contains_key(&TypeId::of::<T>()) {
+                panic!("Asset type not registered: {}", T::TYPE_NAME);
+            }
+
+            let asset_id = AssetId::from_path(&file_path);
             self.paths.insert(file_path.clone(), asset_id);
+            self.known_paths.insert(asset_id, file_path.clone());
+            self.load_states.insert(asset_id, LoadState::Loading);
+            self.asset_types.insert(asset_id, TypeId::of::<T>());
+            self.settings.insert(asset_id, Box::new(settings.clone()));
+            self.priorities.insert(asset_id, priority);
+
+            if let Some(watcher) = &mut self.watcher {
+                Self::watch(watcher, &self.sources, &file_path);
+            }
+
+            let handle = self.strong_handle(asset_id);
+            self.spawn_load::<T>(asset_id, file_path, settings);
+
+            handle
+        }
+    }
+
+    /// Requests every asset listed in the manifest. Named assets stay loaded for the lifetime
+    /// of the `AssetManager`. Entries of types that aren't registered yet are requested once
+    /// their type is registered, invalid entries are logged and skipped.
+    pub fn load_manifest(&mut self, file_path: &str) -> Result<()> {
+        let manifest = Manifest::from_file(file_path)?;
+
+        for (name, entry) in manifest.assets {
+            if self.type_names.contains_key(entry.asset_type.as_str()) {
+                if let Err(e) = self.load_manifest_entry(name, entry) {
+                    log::error!("Failed to load asset manifest entry: {:#}", e);
+                }
+            } else {
+                log::debug!("Deferring {} until asset type '{}' is registered", name, entry.asset_type);
+                self.known_paths.insert(AssetId::from_path(&entry.path), entry.path.clone());
+                self.unresolved.push((name, entry));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_manifest_entry(&mut self, name: String, entry: ManifestEntry) -> Result<()> {
+        let type_id = self.type_names[entry.asset_type.as_str()];
+        let from_manifest = self.registry[&type_id].from_manifest;
+
+        let handle = from_manifest(self, &entry.path, entry.options)
+            .with_context(|| format!("Invalid options for {}", name))?;
+        self.named.insert(name, handle);
+
+        Ok(())
+    }
+
+    fn get_manifest_handle<T: Asset>(&mut self, file_path: &str, options: serde_yaml::Value) -> Result<Box<dyn Any + Send + Sync>> {
+        let settings = match options {
+            serde_yaml::Value::Null => T::Settings::default(),
+            options => serde_yaml::from_value(options)?,
+        };
+
+        Ok(Box::new(self.get_handle_with_settings::<T>(file_path, settings)))
+    }
+
+    /// Returns `None` if no asset of type `T` was declared under `name` in the manifest.
+    pub fn get_named<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        self.named.get(name)?
+            .downcast_ref::<Handle<T>>()
+            .cloned()
+    }
+
+    /// Returns a strong handle if the asset is still loaded.
+    pub fn upgrade<T: Asset>(&self, handle: &Handle<T>) -> Option<Handle<T>> {
+        let token = self.handles.get(&handle.asset_id)?.upgrade()?;
+        Some(Handle::<T>::strong(handle.asset_id, token))
+    }
+
+    /// Turns a weak handle, e.g. one deserialized from a saved scene, back into a strong one.
+    /// Assets that were unloaded are requested again if their path is known, either because
+    /// they were requested by path earlier or are listed in the manifest. Returns `None` for
+    /// anything else, such as assets added in code during a previous run.
+    pub fn resolve<T: Asset>(&mut self, handle: &Handle<T>) -> Option<Handle<T>> {
+        if let Some(handle) = self.upgrade(handle) {
+            return Some(handle);
+        }
+
+        let file_path = self.known_paths.get(&handle.asset_id)?.clone();
+        Some(self.get_handle::<T>(&file_path))
+    }
+
+    /// Unloads every asset without a strong handle left. Primitives and the pool fallbacks are
+    /// never handed out as strong handles, so they are never unloaded.
+    pub fn unload_unused(&mut self) {
+        let unused: Vec<AssetId> = self.handles.iter()
+            .filter(|(_, token)| token.strong_count() == 0)
+            .map(|(&asset_id, _)| asset_id)
+            .collect();
+
+        for asset_id in unused {
+            self.unload(asset_id);
+        }
+    }
+
+    fn unload(&mut self, asset_id: AssetId) {
+        self.handles.remove(&asset_id);
+        self.pending.retain(|(id, _)| *id != asset_id);
+        self.load_states.remove(&asset_id);
+        self.settings.remove(&asset_id);
+        self.priorities.remove(&asset_id);
+        self.reloading.remove(&asset_id);
+        self.stale.remove(&asset_id);
+        self.atlas_regions.retain(|_, &mut id| id != asset_id);
+
+        if let Some(type_id) = self.asset_types.remove(&asset_id) {
+            self.registry.get_mut(&type_id).unwrap().pool.remove(asset_id);
+            self.changes.entry(type_id).or_default().push((asset_id, AssetChange::Removed));
+        }
+
+        self.paths.retain(|file_path, &mut id| {
+            if id != asset_id {
+                return true;
+            }
+            if let Some(watcher) = &mut self.watcher {
+                watcher.unwatch(file_path);
+            }
+            false
+        });
+    }
+
+    fn strong_handle<T: Asset>(&mut self, asset_id: AssetId) -> Handle<T> {
+        let token = match self.handles.get(&asset_id).and_then(Weak::upgrade) {
+            Some(token) => token,
+            None => {
+                let token = Arc::new(());
+                self.handles.insert(asset_id, Arc::downgrade(&token));
+                token
+            }
+        };
+
+        Handle::<T>::strong(asset_id, token)
+    }
+
+    /// Starts watching the files of every requested asset and reloads them in place when they
+    /// change on disk, so existing handles pick up the new content.
+    pub fn watch_for_changes(&mut self) -> Result<()> {
+        if self.watcher.is_some() {
+            return Ok(());
+        }
+
+        let mut watcher = AssetWatcher::new()?;
+        for file_path in self.paths.keys() {
+            Self::watch(&mut watcher, &self.sources, file_path);
+        }
+        self.watcher = Some(watcher);
+
+        Ok(())
+    }
+
+    /// Watches the asset and its `.meta` sidecar, even if it has none yet, so creating or editing
+    /// either reloads it.
+    /// Labeled assets are reloaded when the file they're stored in changes.
+    fn watch(watcher: &mut AssetWatcher, sources: &AssetSources, file_path: &str) {
+        if let (source_path, Some(_)) = super::split_label(file_path) {
+            if let Some(local_path) = sources.local_path(source_path) {
+                watcher.watch(file_path, &local_path);
+            }
+            return;
+        }
+
+        if let Some(local_path) = sources.local_path(file_path) {
+            watcher.watch(file_path, &local_path);
+        }
+
+        if let Some(local_path) = sources.local_path(&meta::meta_path(file_path)) {
+            watcher.watch(file_path, &local_path);
+        }
+    }
 
-            let context = Arc::clone(&self.context);
-            let (tx, rx) = mpsc::channel();
-            self.pending.push(rx);
-
-            let future = async move {
-                match TypeId::of::<T>() {
-                    id if id == TypeId::of::<Texture>() => {
-                        let texture = Texture::load(&context, &file_path).await;
-                        tx.send((asset_id, AssetType::Texture(texture))).unwrap();
-                    },
-                    id if id == TypeId::of::<Mesh>() => {
-                        let mesh = Mesh::load(&context, &file_path).await;
-                        tx.send((asset_id, AssetType::Mesh(mesh))).unwrap();
-                    },
-                    _ => panic!("Invalid asset type"),
+    /// Queues the load on the `AssetLoader`. It's cancelled if every handle to the asset is
+    /// dropped before it starts, so the asset must already have a strong handle.
+    fn spawn_load<T: Asset>(&mut self, asset_id: AssetId, file_path: String, settings: T::Settings) {
+        let context = Arc::clone(&self.context);
+        let sources = Arc::clone(&self.sources);
+        let cache = self.cache.clone();
+        let (tx, rx) = mpsc::channel();
+        self.pending.push((asset_id, Mutex::new(rx)));
+
+        let future = async move {
+            let load = async {
+                let settings = meta::read_settings::<T::Settings>(&sources, &file_path).await?
+                    .unwrap_or(settings);
+
+                match cache {
+                    Some(cache) => cache.load::<T>(&context, &sources, &file_path, &settings).await,
+                    None => T::load(&context, &sources, &file_path, &settings).await,
                 }
-            }.boxed();
-            tokio::spawn(future);
+            };
+            let asset: LoadResult = match AssertUnwindSafe(load).catch_unwind().await {
+                Ok(asset) => asset.map(|asset| Box::new(asset) as Box<dyn Any + Send + Sync>),
+                Err(_) => Err(anyhow!("Asset load panicked")),
+            };
+            // The receiver is gone if the `AssetManager` was dropped meanwhile.
+            let _ = tx.send(asset);
+        }.boxed();
+
+        let token = self.handles.get(&asset_id).cloned().unwrap_or_default();
+        let priority = self.priorities.get(&asset_id).copied().unwrap_or_default();
+        self.loader.spawn(asset_id, priority, token, future);
+
+        if self.loader.is_immediate() {
+            self.receive_loaded();
+        }
+    }
 
-            Handle::<T>::new(asset_id)
+    fn process_changes(&mut self) {
+        let changed = match &self.watcher {
+            Some(watcher) => watcher.changed_paths(),
+            None => return,
+        };
+
+        for file_path in changed {
+            let asset_id = self.paths[&file_path];
+            if !self.reloading.insert(asset_id) {
+                self.stale.insert(asset_id);
+                continue;
+            }
+
+            log::info!("Reloading {}", file_path);
+            let reload = self.registry[&self.asset_types[&asset_id]].reload;
+            reload(self, asset_id, file_path);
         }
     }
 
+    fn reload<T: Asset>(&mut self, asset_id: AssetId, file_path: String) {
+        let settings = self.settings.get(&asset_id)
+            .and_then(|settings| settings.downcast_ref::<T::Settings>())
+            .cloned()
+            .unwrap_or_default();
+
+        self.spawn_load::<T>(asset_id, file_path, settings);
+    }
+
     pub fn process_pending(&mut self) {
+        self.process_changes();
+        self.receive_loaded();
+        self.reload_evicted();
+        self.unload_unused();
+        self.enforce_budgets();
+    }
+
+    /// Sets the approximate GPU memory, in bytes, assets of type `T` may use before the least
+    /// recently used ones are evicted. Only assets loaded from a path that nothing but their
+    /// pool is holding are evicted. They're loaded again the next time they're fetched, which
+    /// returns the pool's default in the meantime. `None` removes the limit.
+    pub fn set_memory_budget<T: Asset>(&mut self, budget: Option<u64>) {
+        self.pool_mut::<T>().set_budget(budget);
+    }
+
+    /// Approximate GPU memory used by loaded assets of type `T`, see `Asset::gpu_size`.
+    pub fn memory_usage<T: Asset>(&self) -> u64 {
+        self.pool::<T>().usage()
+    }
+
+    fn enforce_budgets(&mut self) {
+        for registration in self.registry.values_mut() {
+            let evicted = registration.pool.enforce_budget();
+            if !evicted.is_empty() {
+                log::debug!("Evicted {} assets to stay within the memory budget", evicted.len());
+            }
+        }
+    }
+
+    /// Loads evicted assets that were fetched since the last frame again.
+    fn reload_evicted(&mut self) {
+        let requests: Vec<(AssetId, TypeId)> = self.registry.iter_mut()
+            .flat_map(|(&type_id, registration)| registration.pool.take_reload_requests().into_iter().map(move |asset_id| (asset_id, type_id)))
+            .collect();
+
+        for (asset_id, type_id) in requests {
+            let Some(file_path) = self.known_paths.get(&asset_id).cloned() else { continue };
+            if self.handles.get(&asset_id).and_then(Weak::upgrade).is_none() {
+                continue;
+            }
+
+            log::debug!("Reloading evicted {}", file_path);
+            self.load_states.insert(asset_id, LoadState::Loading);
+            let reload = self.registry[&type_id].reload;
+            reload(self, asset_id, file_path);
+        }
+    }
+
+    /// Moves finished loads into their pools.
+    fn receive_loaded(&mut self) {
+        let mut stale = Vec::new();
         let mut i = 0;
         while i != self.pending.len() {
-            if let Ok((asset_id, asset)) = self.pending[i].try_recv() {
-                match asset {
-                    AssetType::Texture(texture) => {
-                        self.textures.insert(asset_id, texture);
-                    },
-                    AssetType::Mesh(mesh) => {
-                        self.meshes.insert(asset_id, mesh);
-                    },
-                }
-                self.pending.remove(i);
-            } else {
-                i += 1;
+            let (asset_id, receiver) = &mut self.pending[i];
+            let asset_id = *asset_id;
+            let asset = match receiver.get_mut().unwrap().try_recv() {
+                Ok(asset) => asset,
+                Err(TryRecvError::Empty) => {
+                    i += 1;
+                    continue;
+                },
+                // Panics are sent as errors, so the load was cancelled before it ran.
+                Err(TryRecvError::Disconnected) => {
+                    self.pending.remove(i);
+                    continue;
+                },
+            };
+            self.pending.remove(i);
+
+            // Every handle was dropped while the asset was loading, or before the load started.
+            if self.handles.get(&asset_id).and_then(Weak::upgrade).is_none() {
+                continue;
+            }
+
+            // A failed reload keeps showing the previous version instead of the error placeholder.
+            let reloaded = self.reloading.remove(&asset_id);
+            if !reloaded {
+                self.finish_in_groups(asset_id, asset.is_err());
+            } else if self.stale.remove(&asset_id) {
+                stale.push(asset_id);
             }
+
+            let type_id = self.asset_types[&asset_id];
+            let pool = &mut self.registry.get_mut(&type_id).unwrap().pool;
+
+            let (state, change) = match asset {
+                Ok(asset) => {
+                    pool.insert_any(asset_id, asset);
+                    (LoadState::Loaded, if reloaded { AssetChange::Modified } else { AssetChange::Loaded })
+                },
+                Err(e) if reloaded => (LoadState::Loaded, AssetChange::Failed(Self::failed_reload(e))),
+                Err(e) => {
+                    pool.insert_error(asset_id);
+                    let error = Self::failed(e);
+                    (LoadState::Failed(error.clone()), AssetChange::Failed(error))
+                },
+            };
+            self.load_states.insert(asset_id, state);
+            self.changes.entry(type_id).or_default().push((asset_id, change));
+        }
+
+        // Started after the loop, an immediate loader would receive them from inside it.
+        for asset_id in stale {
+            let Some(file_path) = self.known_paths.get(&asset_id).cloned() else { continue };
+            log::info!("Reloading {} again, it changed during the last reload", file_path);
+            self.reloading.insert(asset_id);
+            let reload = self.registry[&self.asset_types[&asset_id]].reload;
+            reload(self, asset_id, file_path);
         }
     }
 
+    /// Creates an empty `LoadGroup`, see `add_to_group`.
+    pub fn create_load_group(&mut self) -> LoadGroup {
+        let group = LoadGroup::default();
+        self.load_groups.push(Arc::downgrade(&group.state));
+
+        group
+    }
+
+    /// Adds `handle`'s asset to `group`, counting it as finished straight away if it isn't loading.
+    /// Weak handles to unloaded assets are `resolve`d, so they're loaded again and waited for.
+    pub fn add_to_group<T: Asset>(&mut self, group: &LoadGroup, handle: &Handle<T>) {
+        let handle = self.resolve(handle).unwrap_or_else(|| handle.clone());
+
+        let mut state = group.state.lock().unwrap();
+        if !state.assets.insert(handle.asset_id) {
+            return;
+        }
+        state.progress.total += 1;
+        state.handles.push(Box::new(handle.clone()));
+
+        match self.load_state(&handle) {
+            LoadState::Loading => {
+                state.remaining.insert(handle.asset_id);
+            },
+            LoadState::Loaded => state.progress.loaded += 1,
+            LoadState::Failed(_) => state.progress.failed += 1,
+        }
+    }
+
+    fn finish_in_groups(&mut self, asset_id: AssetId, failed: bool) {
+        self.load_groups.retain(|group| match group.upgrade() {
+            Some(state) => {
+                state.lock().unwrap().finish(asset_id, failed);
+                true
+            },
+            None => false,
+        });
+    }
+
+    /// Sends an `AssetEvent<T>` to `world` for everything that happened to assets of type `T`
+    /// since the last call, adding the `Events` resource of every registered type if it's
+    /// missing. Call it once per frame after `process_pending`: it also clears events older
+    /// than the previous call, so `Events::update_system` shouldn't run for asset events.
+    pub fn send_events(&mut self, world: &mut World) {
+        for (type_id, registration) in &self.registry {
+            let changes = self.changes.remove(type_id).unwrap_or_default();
+            (registration.send_events)(world, changes);
+        }
+    }
+
+    /// Assets that weren't requested by path (primitives, added or imported assets) are always `Loaded`.
+    pub fn load_state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.load_states.get(&handle.asset_id)
+            .cloned()
+            .unwrap_or(LoadState::Loaded)
+    }
+
+    /// Adds an asset that was created in code rather than loaded from a path.
+    pub fn add<T: Asset>(&mut self, asset: Arc<T>) -> Handle<T> {
+        self.insert_added(AssetId::random(), asset, false)
+    }
+
+    /// Adds an asset an importer read out of another file, under a labeled path such as
+    /// `scene.gltf#mesh0/primitive1`. Its id is derived from the path, so handles to it stay
+    /// valid across runs, and once it's unloaded `resolve` loads it again through `T::load`.
+    /// Adding the same path again replaces the asset.
+    pub fn add_labeled<T: Asset>(&mut self, file_path: &str, asset: Arc<T>, settings: T::Settings) -> Handle<T> {
+        let asset_id = AssetId::from_path(file_path);
+        if self.paths.insert(file_path.to_owned(), asset_id).is_none() {
+            if let Some(watcher) = &mut self.watcher {
+                Self::watch(watcher, &self.sources, file_path);
+            }
+        }
+        self.known_paths.insert(asset_id, file_path.to_owned());
+        self.load_states.insert(asset_id, LoadState::Loaded);
+        self.settings.insert(asset_id, Box::new(settings));
+
+        self.insert_added(asset_id, asset, true)
+    }
+
+    fn insert_added<T: Asset>(&mut self, asset_id: AssetId, asset: Arc<T>, reloadable: bool) -> Handle<T> {
+        match reloadable {
+            true => self.pool_mut::<T>().insert_loaded(asset_id, asset),
+            false => self.pool_mut::<T>().insert(asset_id, asset),
+        }
+
+        let change = match self.asset_types.insert(asset_id, TypeId::of::<T>()) {
+            Some(_) => AssetChange::Modified,
+            None => AssetChange::Loaded,
+        };
+        self.changes.entry(TypeId::of::<T>()).or_default().push((asset_id, change));
+
+        self.strong_handle(asset_id)
+    }
+
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Arc<T> {
+        self.pool::<T>().get(handle.asset_id)
+    }
+
     pub fn get_texture(&self, handle: &Handle<Texture>) -> Arc<Texture> {
-        self.textures.get(handle.asset_id)
+        self.get(handle)
     }
 
     pub fn get_mesh(&self, handle: &Handle<Mesh>) -> Arc<Mesh> {
-        self.meshes.get(handle.asset_id)
+        self.get(handle)
+    }
+
+    pub fn get_cube_texture(&self, handle: &Handle<CubeTexture>) -> Arc<CubeTexture> {
+        self.get(handle)
+    }
+
+    /// Packs `builder`'s images and uploads the pages. Atlases cached offline with
+    /// `PackedAtlas::save` are loaded with `get_handle` instead.
+    pub fn build_atlas(&mut self, builder: &AtlasBuilder, settings: &TextureSettings) -> Result<Handle<TextureAtlas>> {
+        let packed = builder.pack()?;
+        let atlas = TextureAtlas::from_packed(&self.context, &packed, settings)?;
+
+        Ok(self.add(Arc::new(atlas)))
+    }
+
+    /// Returns `None` while the atlas is loading or if it has no region called `name`. Regions
+    /// keep their page alive and still point at the old page after the atlas is reloaded.
+    ///
+    /// Regions of atlases loaded from a path get the labeled path `ui.atlas#name`, so their
+    /// handles can be resolved in a later run. Those are reloaded with a copy of their page
+    /// when the atlas file changes.
+    pub fn get_atlas_region(&mut self, atlas: &Handle<TextureAtlas>, name: &str) -> Option<Handle<AtlasRegion>> {
+        let key = (atlas.asset_id, name.to_owned());
+        if let Some(handle) = self.atlas_regions.get(&key).and_then(|&id| self.upgrade(&Handle::weak(id))) {
+            return Some(handle);
+        }
+
+        let atlas_path = self.known_paths.get(&atlas.asset_id).cloned();
+        let atlas = self.get(atlas);
+        let rect = *atlas.regions.get(name)?;
+        let region = Arc::new(AtlasRegion {
+            texture: atlas.pages[rect.page].clone(),
+            rect,
+        });
+
+        let handle = match atlas_path {
+            Some(atlas_path) => self.add_labeled(&format!("{}#{}", atlas_path, name), region, ()),
+            None => self.insert_added(AssetId::from_path(&format!("{}#{}", key.0, name)), region, false),
+        };
+        self.atlas_regions.insert(key, handle.asset_id);
+
+        Some(handle)
     }
 
     pub fn get_primitive_handle(&self, primitive_mesh: PrimitiveMesh) -> Handle<Mesh> {
-        Handle::<Mesh>::new(primitive_mesh as usize)
+        Handle::<Mesh>::weak(primitive_mesh.asset_id())
+    }
+
+    /// A handle to no asset, so it always fetches the pool's default, e.g. in place of an
+    /// asset missing from the manifest.
+    pub fn get_default_handle<T: Asset>(&self) -> Handle<T> {
+        Handle::<T>::weak(AssetId::from_path("default://"))
+    }
+
+    /// Generates a primitive at a custom size or resolution, e.g.
+    /// `add_primitive(&UvSphere::new().with_segments(64, 32))`.
+    pub fn add_primitive(&mut self, primitive: &impl Primitive) -> Result<Handle<Mesh>> {
+        let mesh = Mesh::from_primitive(&self.context, &primitive.generate())?;
+        Ok(self.add(Arc::new(mesh)))
     }
 
     pub fn get_primitive_mesh(&self, primitive_mesh: PrimitiveMesh) -> Arc<Mesh> {
-        self.meshes.get(primitive_mesh as usize)
+        self.pool::<Mesh>().get(primitive_mesh.asset_id())
     }
 
-    fn get_new_id(&mut self) -> usize {
-        let id = self.next_id;
-        self.next_id += 1;
+    fn pool<T: Asset>(&self) -> &AssetPool<T> {
+        self.registry.get(&TypeId::of::<T>())
+            .and_then(|registration| registration.pool.as_any().downcast_ref())
+            .unwrap_or_else(|| panic!("Asset type not registered: {}", T::TYPE_NAME))
+    }
+
+    fn pool_mut<T: Asset>(&mut self) -> &mut AssetPool<T> {
+        self.registry.get_mut(&TypeId::of::<T>())
+            .and_then(|registration| registration.pool.as_any_mut().downcast_mut())
+            .unwrap_or_else(|| panic!("Asset type not registered: {}", T::TYPE_NAME))
+    }
+
+    fn failed(error: anyhow::Error) -> Arc<anyhow::Error> {
+        log::error!("Failed to load asset: {:#}", error);
+        Arc::new(error)
+    }
 
-        id
+    fn failed_reload(error: anyhow::Error) -> Arc<anyhow::Error> {
+        log::error!("Failed to reload asset, keeping previous version: {:#}", error);
+        Arc::new(error)
     }
 }
\ No newline at end of file
@@ -0,0 +1,417 @@
+use std::{collections::BTreeMap, io::{Cursor, Read}, path::Path, sync::Arc};
+
+use anyhow::{bail, Context as _, Result};
+use async_trait::async_trait;
+
+use crate::engine::context::Context;
+
+use super::{Asset, sources::{AssetSources, archive::read_u32}, texture::TextureSettings, Texture};
+
+const MAGIC: &[u8; 4] = b"RATL";
+const VERSION: u32 = 1;
+
+/// Where a packed image ended up in its atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRect {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// UV of the top left corner.
+    pub uv_min: glam::Vec2,
+    /// UV of the bottom right corner.
+    pub uv_max: glam::Vec2,
+}
+
+/// Packs images into as few pages as fit in `max_size` using a shelf packer.
+pub struct AtlasBuilder {
+    images: Vec<(String, image::RgbaImage)>,
+    max_size: u32,
+    padding: u32,
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            max_size: 2048,
+            padding: 1,
+        }
+    }
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest width and height of a page.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Transparent gap left around each image so filtering doesn't bleed into its neighbours.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, image: &image::DynamicImage) {
+        self.images.push((name.into(), image.to_rgba8()));
+    }
+
+    /// Adds an image file under its file name without the extension.
+    pub fn add_file(&mut self, file_path: impl AsRef<Path>) -> Result<()> {
+        let file_path = file_path.as_ref();
+        let name = file_path.file_stem()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("Invalid image path: {}", file_path.display()))?;
+
+        let image = image::open(file_path)
+            .with_context(|| format!("Unable to decode image: {}", file_path.display()))?;
+        self.add(name, &image);
+
+        Ok(())
+    }
+
+    pub fn pack(&self) -> Result<PackedAtlas> {
+        let padding = self.padding;
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse((self.images[i].1.height(), self.images[i].1.width())));
+
+        // (page, x, y) of each image by index, used extent of each page, and the current shelf.
+        let mut placements = vec![(0, 0, 0); self.images.len()];
+        let mut extents = vec![(0, 0)];
+        let (mut shelf_x, mut shelf_y, mut shelf_height) = (padding, padding, 0);
+
+        for i in order {
+            let (name, image) = &self.images[i];
+            let (width, height) = image.dimensions();
+            if width + 2 * padding > self.max_size || height + 2 * padding > self.max_size {
+                bail!("{} ({}x{}) does not fit in a {} atlas page", name, width, height, self.max_size);
+            }
+
+            if shelf_x + width + padding > self.max_size {
+                shelf_x = padding;
+                shelf_y += shelf_height + padding;
+                shelf_height = 0;
+            }
+            if shelf_y + height + padding > self.max_size {
+                extents.push((0, 0));
+                shelf_x = padding;
+                shelf_y = padding;
+                shelf_height = 0;
+            }
+
+            let page = extents.len() - 1;
+            placements[i] = (page, shelf_x, shelf_y);
+            extents[page].0 = extents[page].0.max(shelf_x + width + padding);
+            extents[page].1 = extents[page].1.max(shelf_y + height + padding);
+
+            shelf_x += width + padding;
+            shelf_height = shelf_height.max(height);
+        }
+
+        let mut pages: Vec<image::RgbaImage> = extents.iter()
+            .map(|&(width, height)| {
+                let size = |extent: u32| extent.max(1).next_power_of_two().min(self.max_size);
+                image::RgbaImage::new(size(width), size(height))
+            })
+            .collect();
+
+        let mut regions = BTreeMap::new();
+        for ((name, image), &(page, x, y)) in self.images.iter().zip(&placements) {
+            image::imageops::replace(&mut pages[page], image, x as i64, y as i64);
+            regions.insert(name.clone(), PackedAtlas::rect(&pages[page], page, x, y, image.width(), image.height()));
+        }
+
+        Ok(PackedAtlas {
+            pages,
+            regions,
+        })
+    }
+}
+
+/// The output of `AtlasBuilder::pack`, which can be cached to a `.atlas` file and loaded
+/// back as a `TextureAtlas`.
+///
+/// Layout (little-endian): `RATL`, version `u32`, page count `u32`, then per page the PNG
+/// length `u32` and PNG data, then region count `u32` and per region the name length `u32`,
+/// UTF-8 name, and page, x, y, width and height as `u32`.
+pub struct PackedAtlas {
+    pub pages: Vec<image::RgbaImage>,
+    pub regions: BTreeMap<String, AtlasRect>,
+}
+
+impl PackedAtlas {
+    fn rect(page_image: &image::RgbaImage, page: usize, x: u32, y: u32, width: u32, height: u32) -> AtlasRect {
+        let page_size = glam::vec2(page_image.width() as f32, page_image.height() as f32);
+
+        AtlasRect {
+            page,
+            x,
+            y,
+            width,
+            height,
+            uv_min: glam::vec2(x as f32, y as f32) / page_size,
+            uv_max: glam::vec2((x + width) as f32, (y + height) as f32) / page_size,
+        }
+    }
+
+    pub fn save(&self, file_path: impl AsRef<Path>) -> Result<()> {
+        let file_path = file_path.as_ref();
+        std::fs::write(file_path, self.to_bytes()?)
+            .with_context(|| format!("Unable to write file: {}", file_path.display()))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+        bytes.extend_from_slice(&(self.pages.len() as u32).to_le_bytes());
+        for page in &self.pages {
+            let mut png = Vec::new();
+            page.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)?;
+            bytes.extend_from_slice(&(png.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&png);
+        }
+
+        bytes.extend_from_slice(&(self.regions.len() as u32).to_le_bytes());
+        for (name, rect) in &self.regions {
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            for value in [rect.page as u32, rect.x, rect.y, rect.width, rect.height] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = bytes;
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a reclipse atlas");
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            bail!("unsupported atlas version {}", version);
+        }
+
+        let mut pages = Vec::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let mut png = vec![0; read_u32(&mut reader)? as usize];
+            reader.read_exact(&mut png)?;
+            pages.push(image::load_from_memory(&png)?.to_rgba8());
+        }
+
+        let mut regions = BTreeMap::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let mut name = vec![0; read_u32(&mut reader)? as usize];
+            reader.read_exact(&mut name)?;
+
+            let name = String::from_utf8(name)?;
+            let page = read_u32(&mut reader)? as usize;
+            let x = read_u32(&mut reader)?;
+            let y = read_u32(&mut reader)?;
+            let width = read_u32(&mut reader)?;
+            let height = read_u32(&mut reader)?;
+
+            let page_image = pages.get(page)
+                .with_context(|| format!("region {} is on missing page {}", name, page))?;
+            let rect = Self::rect(page_image, page, x, y, width, height);
+            regions.insert(name, rect);
+        }
+
+        Ok(Self {
+            pages,
+            regions,
+        })
+    }
+}
+
+/// Packed images uploaded as one texture per page. Individual images are looked up with
+/// `AssetManager::get_atlas_region`.
+#[derive(Default)]
+pub struct TextureAtlas {
+    pub pages: Vec<Arc<Texture>>,
+    pub regions: BTreeMap<String, AtlasRect>,
+}
+
+impl TextureAtlas {
+    /// Pages are uploaded without mipmaps whatever `settings.mipmaps` says, since the smaller
+    /// mips would blend neighbouring regions together.
+    pub fn from_packed(context: &Context, packed: &PackedAtlas, settings: &TextureSettings) -> Result<Self> {
+        let pages = packed.pages.iter()
+            .map(|page| Self::page_texture(context, page, settings))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            pages,
+            regions: packed.regions.clone(),
+        })
+    }
+
+    fn page_texture(context: &Context, page: &image::RgbaImage, settings: &TextureSettings) -> Result<Arc<Texture>> {
+        let settings = TextureSettings { mipmaps: false, ..settings.clone() };
+        Texture::from_image(context, &image::DynamicImage::ImageRgba8(page.clone()), &settings).map(Arc::new)
+    }
+}
+
+/// A single image in a `TextureAtlas`: the page texture it was packed into and where.
+pub struct AtlasRegion {
+    pub texture: Arc<Texture>,
+    pub rect: AtlasRect,
+}
+
+impl AtlasRegion {
+    /// A region covering all of `texture`.
+    pub fn whole(texture: Arc<Texture>) -> Self {
+        let (width, height) = (texture.texture.width(), texture.texture.height());
+
+        Self {
+            texture,
+            rect: AtlasRect {
+                page: 0,
+                x: 0,
+                y: 0,
+                width,
+                height,
+                uv_min: glam::Vec2::ZERO,
+                uv_max: glam::Vec2::ONE,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Asset for TextureAtlas {
+    const TYPE_NAME: &'static str = "atlas";
+    type Settings = TextureSettings;
+
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, settings: &TextureSettings) -> Result<Arc<Self>> {
+        let bytes = sources.read(file_path).await?;
+        let packed = PackedAtlas::from_bytes(&bytes)
+            .with_context(|| format!("Invalid atlas: {}", file_path))?;
+
+        Ok(Arc::new(TextureAtlas::from_packed(context, &packed, settings)?))
+    }
+}
+
+#[async_trait]
+impl Asset for AtlasRegion {
+    const TYPE_NAME: &'static str = "atlas_region";
+    type Settings = ();
+
+    /// Loads the page a region is on by itself, from a path labeled with the region's name,
+    /// e.g. `ui.atlas#button`. Regions are normally created with `AssetManager::get_atlas_region`.
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, _settings: &()) -> Result<Arc<Self>> {
+        let (atlas_path, Some(name)) = super::split_label(file_path) else {
+            bail!("{}: atlas region paths need the region name as a label", file_path);
+        };
+
+        let bytes = sources.read(atlas_path).await?;
+        let packed = PackedAtlas::from_bytes(&bytes)
+            .with_context(|| format!("Invalid atlas: {}", atlas_path))?;
+        let rect = *packed.regions.get(name)
+            .with_context(|| format!("{}: no region called {}", atlas_path, name))?;
+
+        Ok(Arc::new(AtlasRegion {
+            texture: TextureAtlas::page_texture(context, &packed.pages[rect.page], &TextureSettings::default())?,
+            rect,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, value: u8) -> image::DynamicImage {
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(width, height, image::Rgba([value; 4])))
+    }
+
+    fn overlaps(a: &AtlasRect, b: &AtlasRect) -> bool {
+        a.page == b.page && a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn packs_without_overlap_and_keeps_padding() {
+        let mut builder = AtlasBuilder::new().with_max_size(64).with_padding(2);
+        for (i, (width, height)) in [(20, 10), (8, 30), (16, 16), (5, 5), (30, 4), (12, 12)].into_iter().enumerate() {
+            builder.add(format!("image{}", i), &solid(width, height, i as u8 + 1));
+        }
+        let packed = builder.pack().unwrap();
+
+        let rects: Vec<&AtlasRect> = packed.regions.values().collect();
+        for (i, a) in rects.iter().enumerate() {
+            let page = &packed.pages[a.page];
+            assert!(a.x >= 2 && a.y >= 2);
+            assert!(a.x + a.width + 2 <= page.width() && a.y + a.height + 2 <= page.height());
+            for b in &rects[i + 1..] {
+                assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        for (i, (name, rect)) in packed.regions.iter().enumerate() {
+            assert_eq!(name, &format!("image{}", i));
+            let page = &packed.pages[rect.page];
+            assert_eq!(page.get_pixel(rect.x, rect.y).0, [i as u8 + 1; 4]);
+            assert_eq!(page.get_pixel(rect.x + rect.width - 1, rect.y + rect.height - 1).0, [i as u8 + 1; 4]);
+        }
+    }
+
+    #[test]
+    fn computes_uvs_from_the_page_size() {
+        let mut builder = AtlasBuilder::new().with_padding(1);
+        builder.add("square", &solid(14, 6, 1));
+        let packed = builder.pack().unwrap();
+
+        // 16x8 with padding, already powers of two.
+        let rect = packed.regions["square"];
+        assert_eq!(packed.pages[0].dimensions(), (16, 8));
+        assert_eq!(rect.uv_min, glam::vec2(1.0 / 16.0, 1.0 / 8.0));
+        assert_eq!(rect.uv_max, glam::vec2(15.0 / 16.0, 7.0 / 8.0));
+    }
+
+    #[test]
+    fn starts_a_new_page_when_full() {
+        let mut builder = AtlasBuilder::new().with_max_size(32).with_padding(0);
+        for i in 0..5 {
+            builder.add(format!("image{}", i), &solid(16, 16, 1));
+        }
+        let packed = builder.pack().unwrap();
+
+        assert_eq!(packed.pages.len(), 2);
+        assert_eq!(packed.regions.values().filter(|rect| rect.page == 1).count(), 1);
+    }
+
+    #[test]
+    fn rejects_images_larger_than_a_page() {
+        let mut builder = AtlasBuilder::new().with_max_size(32).with_padding(1);
+        builder.add("large", &solid(31, 8, 1));
+
+        assert!(builder.pack().is_err());
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let mut builder = AtlasBuilder::new().with_max_size(32);
+        builder.add("a", &solid(10, 6, 1));
+        builder.add("b", &solid(4, 12, 2));
+        let packed = builder.pack().unwrap();
+
+        let loaded = PackedAtlas::from_bytes(&packed.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.regions, packed.regions);
+        assert_eq!(loaded.pages, packed.pages);
+
+        assert!(PackedAtlas::from_bytes(b"RPAK").is_err());
+    }
+}
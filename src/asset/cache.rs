@@ -0,0 +1,104 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use anyhow::{Context as _, Result};
+
+use crate::engine::context::Context;
+
+use super::{Asset, sources::AssetSources};
+
+/// Conventional cache directory, relative to the working directory.
+pub const DEFAULT_CACHE_DIR: &str = ".cache/assets";
+
+/// Part of every cache key. Bump it when cooking changes in a way the cooked formats'
+/// own versions don't catch, e.g. better mesh optimization, so old entries are recooked.
+const CACHE_VERSION: u32 = 1;
+
+/// Cooked assets on disk, see `Asset::cook`. Entries are named after a hash of the source
+/// bytes, the asset type and the import settings, so editing any of them cooks a new entry
+/// on the next load. Old entries are never cleaned up, deleting the directory is always safe.
+/// Labeled sub-assets, such as glTF primitives and images, are always loaded from source.
+///
+/// Assets are cooked the first time they're loaded. To ship them precooked, run `cook` over
+/// every asset offline and package the cache directory with the game.
+#[derive(Clone)]
+pub struct AssetCache {
+    dir: PathBuf,
+}
+
+impl AssetCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+        }
+    }
+
+    /// Loads the cooked form of `file_path` if it is up to date, otherwise cooks and caches it
+    /// first. Types and files that can't be cooked are loaded from source.
+    pub async fn load<T: Asset>(&self, context: &Context, sources: &AssetSources, file_path: &str, settings: &T::Settings) -> Result<Arc<T>> {
+        if !T::cookable(file_path) {
+            return T::load(context, sources, file_path, settings).await;
+        }
+
+        let bytes = sources.read(file_path).await?;
+        let entry_path = self.entry_path::<T>(&bytes, settings)?;
+
+        if let Ok(cooked) = std::fs::read(&entry_path) {
+            match T::from_cooked(context, &cooked, settings) {
+                Ok(asset) => return Ok(asset),
+                Err(e) => log::warn!("Recooking {}, cached copy is invalid: {:#}", file_path, e),
+            }
+        }
+
+        let cooked = T::cook(file_path, &bytes, settings)?;
+        if let Err(e) = self.write(&entry_path, &cooked) {
+            log::warn!("Unable to cache {}: {:#}", file_path, e);
+        }
+
+        T::from_cooked(context, &cooked, settings)
+    }
+
+    /// Cooks `file_path` without uploading it. Returns `false` if it was already up to date
+    /// or can't be cooked.
+    pub async fn cook<T: Asset>(&self, sources: &AssetSources, file_path: &str, settings: &T::Settings) -> Result<bool> {
+        if !T::cookable(file_path) {
+            return Ok(false);
+        }
+
+        let bytes = sources.read(file_path).await?;
+        let entry_path = self.entry_path::<T>(&bytes, settings)?;
+        if entry_path.exists() {
+            return Ok(false);
+        }
+
+        let cooked = T::cook(file_path, &bytes, settings)?;
+        self.write(&entry_path, &cooked)?;
+
+        Ok(true)
+    }
+
+    fn entry_path<T: Asset>(&self, bytes: &[u8], settings: &T::Settings) -> Result<PathBuf> {
+        let settings = serde_yaml::to_string(settings)
+            .context("Unable to serialize import settings")?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&CACHE_VERSION.to_le_bytes());
+        for part in [T::TYPE_NAME.as_bytes(), settings.as_bytes(), bytes] {
+            hasher.update(&(part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+
+        Ok(self.dir.join(format!("{}.{}", hasher.finalize().to_hex(), T::TYPE_NAME)))
+    }
+
+    /// Writes to a temporary file first so a crash or a concurrent load never sees half an entry.
+    fn write(&self, entry_path: &Path, cooked: &[u8]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Unable to create directory: {}", self.dir.display()))?;
+
+        let temp_path = entry_path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&temp_path, cooked)
+            .with_context(|| format!("Unable to write file: {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, entry_path)
+            .with_context(|| format!("Unable to write file: {}", entry_path.display()))
+    }
+}
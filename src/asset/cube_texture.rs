@@ -0,0 +1,264 @@
+use std::{f32::consts::{PI, TAU}, sync::Arc};
+
+use anyhow::{bail, Context as _, Result};
+use async_trait::async_trait;
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{context::Context, renderer::Renderer};
+
+use super::{Asset, sources::AssetSources, Texture};
+
+/// Placeholder replaced by each of `CubeTextureSettings::faces` to find the six face images.
+pub const FACE_PLACEHOLDER: &str = "{face}";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CubeTextureSettings {
+    /// Names substituted for `{face}` in six-face paths, in +X, -X, +Y, -Y, +Z, -Z order.
+    pub faces: [String; 6],
+    /// Edge length of faces converted from an equirectangular panorama, 0 uses a quarter of its width.
+    pub face_size: u32,
+    pub mipmaps: bool,
+}
+
+impl Default for CubeTextureSettings {
+    fn default() -> Self {
+        Self {
+            faces: ["px", "nx", "py", "ny", "pz", "nz"].map(String::from),
+            face_size: 0,
+            mipmaps: true,
+        }
+    }
+}
+
+/// A cube map, loaded from one of:
+/// - six face images, with `{face}` in the path, e.g. `res/skybox/{face}.png`
+/// - a 3:4 vertical cross with +Y on top, -X, +Z, +X across the middle, then -Y and a flipped -Z
+/// - a 2:1 equirectangular panorama, converted to faces on load
+///
+/// HDR images are stored as `Rgba16Float`, everything else as `Rgba8UnormSrgb`.
+pub struct CubeTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl CubeTexture {
+    /// Builds a cube map from six square faces of equal size, in +X, -X, +Y, -Y, +Z, -Z order.
+    pub fn from_faces(context: &Context, faces: &[DynamicImage], settings: &CubeTextureSettings) -> Result<Self> {
+        if faces.len() != 6 {
+            bail!("expected 6 cube faces, got {}", faces.len());
+        }
+
+        let (size, _) = faces[0].dimensions();
+        if faces.iter().any(|face| face.dimensions() != (size, size)) {
+            bail!("cube faces must be square and share the same size");
+        }
+
+        let hdr = faces.iter().any(is_hdr);
+        let (format, levels): (_, Vec<Vec<Vec<u8>>>) = if hdr {
+            (wgpu::TextureFormat::Rgba16Float, faces.iter()
+                .map(|face| Texture::mip_chain(face.to_rgba32f(), settings.mipmaps, Texture::encode_half))
+                .collect())
+        } else {
+            (wgpu::TextureFormat::Rgba8UnormSrgb, faces.iter()
+                .map(|face| Texture::mip_chain(face.to_rgba8(), settings.mipmaps, |level| level.to_vec()))
+                .collect())
+        };
+
+        let size = wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        };
+        let mip_level_count = levels[0].len() as u32;
+
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("cube_texture"),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let texel_size = format.block_size(None).unwrap_or(4);
+        for (layer, face_levels) in levels.iter().enumerate() {
+            for (mip_level, data) in face_levels.iter().enumerate() {
+                let mip_level = mip_level as u32;
+                let mip_size = wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size.mip_level_size(mip_level, wgpu::TextureDimension::D2)
+                };
+
+                context.queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        aspect: wgpu::TextureAspect::All,
+                        texture: &texture,
+                        mip_level,
+                        origin: wgpu::Origin3d { x: 0, y: 0, z: layer as u32 },
+                    },
+                    data,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(mip_size.width * texel_size),
+                        rows_per_image: Some(mip_size.height),
+                    },
+                    mip_size,
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = context.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &Renderer::get_cube_texture_layout(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                }
+            ],
+            label: Some("cube_texture_bind_group"),
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        })
+    }
+
+    /// Cuts the faces out of a 3:4 vertical cross.
+    pub fn faces_from_cross(cross: &DynamicImage) -> Vec<DynamicImage> {
+        let size = cross.width() / 3;
+        let face = |x: u32, y: u32| cross.crop_imm(x * size, y * size, size, size);
+
+        vec![face(2, 1), face(0, 1), face(1, 0), face(1, 2), face(1, 1), face(1, 3).rotate180()]
+    }
+
+    /// Projects a 2:1 equirectangular panorama onto six `face_size` faces. The centre of the
+    /// panorama ends up facing -Z.
+    pub fn faces_from_equirectangular(panorama: &DynamicImage, face_size: u32) -> Vec<DynamicImage> {
+        let source = panorama.to_rgba32f();
+
+        (0..6)
+            .map(|face| {
+                let img = image::Rgba32FImage::from_fn(face_size, face_size, |x, y| {
+                    let u = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+                    let v = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+                    let direction = face_direction(face, u, v).normalize();
+
+                    let s = 0.5 + direction.x.atan2(-direction.z) / TAU;
+                    let t = direction.y.clamp(-1.0, 1.0).acos() / PI;
+                    sample_bilinear(&source, s, t)
+                });
+
+                if is_hdr(panorama) {
+                    DynamicImage::ImageRgba32F(img)
+                } else {
+                    DynamicImage::ImageRgba8(DynamicImage::ImageRgba32F(img).to_rgba8())
+                }
+            })
+            .collect()
+    }
+}
+
+fn is_hdr(img: &DynamicImage) -> bool {
+    matches!(img, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+/// Direction through texel `(u, v)` in `[-1, 1]` of cube `face`, following the wgpu face layout.
+fn face_direction(face: usize, u: f32, v: f32) -> glam::Vec3 {
+    match face {
+        0 => glam::vec3(1.0, -v, -u),
+        1 => glam::vec3(-1.0, -v, u),
+        2 => glam::vec3(u, 1.0, v),
+        3 => glam::vec3(u, -1.0, -v),
+        4 => glam::vec3(u, -v, 1.0),
+        _ => glam::vec3(-u, -v, -1.0),
+    }
+}
+
+/// Samples `img` at `(s, t)` in `[0, 1]`, wrapping horizontally and clamping vertically.
+fn sample_bilinear(img: &image::Rgba32FImage, s: f32, t: f32) -> image::Rgba<f32> {
+    let (width, height) = img.dimensions();
+    let x = s * width as f32 - 0.5;
+    let y = (t * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let column = |x: f32| (x as i64).rem_euclid(width as i64) as u32;
+    let (x0, x1) = (column(x0), column(x0 + 1.0));
+    let (y0, y1) = (y0 as u32, (y0 as u32 + 1).min(height - 1));
+
+    let texel = |x, y| glam::Vec4::from_array(img.get_pixel(x, y).0);
+    let top = texel(x0, y0).lerp(texel(x1, y0), fx);
+    let bottom = texel(x0, y1).lerp(texel(x1, y1), fx);
+
+    image::Rgba(top.lerp(bottom, fy).to_array())
+}
+
+#[async_trait]
+impl Asset for CubeTexture {
+    const TYPE_NAME: &'static str = "cube_texture";
+    type Settings = CubeTextureSettings;
+
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, settings: &CubeTextureSettings) -> Result<Arc<Self>> {
+        let faces = if file_path.contains(FACE_PLACEHOLDER) {
+            let mut faces = Vec::with_capacity(6);
+            for face in &settings.faces {
+                let face_path = file_path.replace(FACE_PLACEHOLDER, face);
+                let bytes = sources.read(&face_path).await?;
+                faces.push(image::load_from_memory(&bytes)
+                    .with_context(|| format!("Unable to decode cube face: {}", face_path))?);
+            }
+            faces
+        } else {
+            let bytes = sources.read(file_path).await?;
+            let img = image::load_from_memory(&bytes)
+                .with_context(|| format!("Unable to decode cube texture: {}", file_path))?;
+
+            match img.dimensions() {
+                (width, height) if width * 4 == height * 3 => CubeTexture::faces_from_cross(&img),
+                (width, height) if width == height * 2 => {
+                    let face_size = match settings.face_size {
+                        0 => width / 4,
+                        face_size => face_size,
+                    };
+                    CubeTexture::faces_from_equirectangular(&img, face_size)
+                }
+                (width, height) => bail!(
+                    "{}: expected a 3:4 vertical cross or a 2:1 equirectangular panorama, got {}x{}",
+                    file_path, width, height,
+                ),
+            }
+        };
+
+        let cube_texture = CubeTexture::from_faces(context, &faces, settings)
+            .with_context(|| format!("Unable to create cube texture: {}", file_path))?;
+
+        Ok(Arc::new(cube_texture))
+    }
+}
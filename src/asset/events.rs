@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use bevy_ecs::prelude::*;
+
+use super::{Asset, handle::{AssetId, Handle}};
+
+/// Sent to the `World` by `AssetManager::send_events`, e.g. to rebuild bind groups once a
+/// texture is reloaded. Handles are weak, `AssetManager::upgrade` them to keep the asset.
+#[derive(Event)]
+pub enum AssetEvent<T: Asset> {
+    /// Finished loading, or was added in code.
+    Loaded { handle: Handle<T> },
+    /// Was reloaded in place after its file changed.
+    Modified { handle: Handle<T> },
+    /// Failed to load or to reload. A failed reload keeps showing the previous version.
+    Failed { handle: Handle<T>, error: Arc<anyhow::Error> },
+    /// Was unloaded after its last strong handle was dropped.
+    Removed { handle: Handle<T> },
+}
+
+impl<T: Asset> AssetEvent<T> {
+    pub fn handle(&self) -> &Handle<T> {
+        match self {
+            AssetEvent::Loaded { handle }
+            | AssetEvent::Modified { handle }
+            | AssetEvent::Failed { handle, .. }
+            | AssetEvent::Removed { handle } => handle,
+        }
+    }
+
+    fn new(asset_id: AssetId, change: AssetChange) -> Self {
+        let handle = Handle::weak(asset_id);
+        match change {
+            AssetChange::Loaded => AssetEvent::Loaded { handle },
+            AssetChange::Modified => AssetEvent::Modified { handle },
+            AssetChange::Failed(error) => AssetEvent::Failed { handle, error },
+            AssetChange::Removed => AssetEvent::Removed { handle },
+        }
+    }
+}
+
+/// An `AssetEvent` without its asset type, queued by the `AssetManager` until `send_events`.
+pub(crate) enum AssetChange {
+    Loaded,
+    Modified,
+    Failed(Arc<anyhow::Error>),
+    Removed,
+}
+
+/// Swaps the `Events<AssetEvent<T>>` buffers, adding the resource if it's missing, then sends
+/// `changes`. Events stay readable until the second call after they were sent.
+pub(crate) fn send<T: Asset>(world: &mut World, changes: Vec<(AssetId, AssetChange)>) {
+    let mut events = world.get_resource_or_insert_with(Events::<AssetEvent<T>>::default);
+    events.update();
+    events.extend(changes.into_iter().map(|(asset_id, change)| AssetEvent::new(asset_id, change)));
+}
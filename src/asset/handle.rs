@@ -1,21 +1,129 @@
-use std::marker::PhantomData;
+use std::{fmt, hash::{Hash, Hasher}, marker::PhantomData, sync::Arc};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
 
 use super::Asset;
 
+/// Namespace for ids derived from asset paths, see `AssetId::from_path`.
+const PATH_NAMESPACE: Uuid = Uuid::from_u128(0x6c1e_94a0_3f5b_4d8e_a2d7_0b9c_51f8_e3a6);
+
+/// Identifies an asset across runs. Assets requested by path get an id derived from the path,
+/// so the same file has the same id every time. Assets created in code get a random one.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AssetId(Uuid);
+
+impl AssetId {
+    pub fn from_path(file_path: &str) -> Self {
+        Self(Uuid::new_v5(&PATH_NAMESPACE, file_path.as_bytes()))
+    }
+
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl From<Uuid> for AssetId {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl fmt::Debug for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AssetId({})", self.0)
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A reference to an asset in the `AssetManager`.
+///
+/// Strong handles keep their asset loaded; once the last one is dropped the asset is
+/// unloaded by `AssetManager::process_pending`. Weak handles never keep an asset alive.
+///
+/// Handles compare and hash by `asset_id` alone, and serialize as it. Deserialized handles are
+/// weak, `AssetManager::resolve` turns them back into strong ones.
 pub struct Handle<T: Asset> {
-    pub asset_id: usize,
+    pub asset_id: AssetId,
+    strong: Option<Arc<()>>,
     _marker: PhantomData<T>,
 }
 
 impl<T: Asset> Handle<T> {
-    pub fn new(asset_id: usize) -> Self {
+    pub fn weak(asset_id: AssetId) -> Self {
+        Self {
+            asset_id,
+            strong: None,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn strong(asset_id: AssetId, token: Arc<()>) -> Self {
         Self {
             asset_id,
+            strong: Some(token),
             _marker: PhantomData,
         }
     }
 
-    pub fn clone(&self) -> Handle<T> {
-        Self::new(self.asset_id)
+    pub fn is_strong(&self) -> bool {
+        self.strong.is_some()
     }
-}
\ No newline at end of file
+
+    pub fn downgrade(&self) -> Handle<T> {
+        Self::weak(self.asset_id)
+    }
+}
+
+impl<T: Asset> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            asset_id: self.asset_id,
+            strong: self.strong.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Asset> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.asset_id == other.asset_id
+    }
+}
+
+impl<T: Asset> Eq for Handle<T> {}
+
+impl<T: Asset> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.asset_id.hash(state);
+    }
+}
+
+impl<T: Asset> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strength = if self.is_strong() { "strong" } else { "weak" };
+        write!(f, "Handle<{}>({}, {})", T::TYPE_NAME, self.asset_id, strength)
+    }
+}
+
+impl<T: Asset> Serialize for Handle<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.asset_id.serialize(serializer)
+    }
+}
+
+impl<'de, T: Asset> Deserialize<'de> for Handle<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        AssetId::deserialize(deserializer).map(Self::weak)
+    }
+}
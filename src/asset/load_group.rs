@@ -0,0 +1,106 @@
+use std::{any::Any, collections::HashSet, future::Future, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll, Waker}};
+
+use bevy_ecs::prelude::*;
+
+use super::handle::AssetId;
+
+/// How many assets of a `LoadGroup` have finished. Failed assets count as finished, so a group
+/// with failures still completes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    pub fn is_done(&self) -> bool {
+        self.loaded + self.failed == self.total
+    }
+
+    /// Finished assets over the total, 1 for an empty group.
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => (self.loaded + self.failed) as f32 / total as f32,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LoadGroupState {
+    pub(crate) progress: LoadProgress,
+    pub(crate) assets: HashSet<AssetId>,
+    /// Assets in `assets` that are still loading.
+    pub(crate) remaining: HashSet<AssetId>,
+    /// Strong handles keeping the group's assets from being unloaded.
+    pub(crate) handles: Vec<Box<dyn Any + Send + Sync>>,
+    wakers: Vec<Waker>,
+}
+
+impl LoadGroupState {
+    /// Counts `asset_id` as finished if the group was waiting on it.
+    pub(crate) fn finish(&mut self, asset_id: AssetId, failed: bool) {
+        if !self.remaining.remove(&asset_id) {
+            return;
+        }
+
+        match failed {
+            true => self.progress.failed += 1,
+            false => self.progress.loaded += 1,
+        }
+
+        if self.remaining.is_empty() {
+            self.wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+/// A set of assets loaded together, e.g. everything a level needs behind a loading screen.
+/// Created with `AssetManager::create_load_group` and filled with `AssetManager::add_to_group`,
+/// which keeps the added assets loaded for as long as the group exists.
+///
+/// Progress is updated by `AssetManager::process_pending`, so `wait` only completes while
+/// something keeps calling it. Groups can also be attached to an entity for systems to read.
+#[derive(Component, Clone, Default)]
+pub struct LoadGroup {
+    pub(crate) state: Arc<Mutex<LoadGroupState>>,
+}
+
+impl LoadGroup {
+    pub fn progress(&self) -> LoadProgress {
+        self.state.lock().unwrap().progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress().is_done()
+    }
+
+    /// Completes with the final progress once every asset in the group has loaded or failed.
+    pub fn wait(&self) -> LoadGroupFuture {
+        LoadGroupFuture {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+pub struct LoadGroupFuture {
+    state: Arc<Mutex<LoadGroupState>>,
+}
+
+impl Future for LoadGroupFuture {
+    type Output = LoadProgress;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<LoadProgress> {
+        let mut state = self.state.lock().unwrap();
+        if state.remaining.is_empty() {
+            return Poll::Ready(state.progress);
+        }
+
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,164 @@
+use std::{panic::AssertUnwindSafe, sync::{Arc, Condvar, Mutex, Weak}, thread};
+
+use futures::{future::BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+
+use super::handle::AssetId;
+
+/// Order queued loads start in. Loads with the same priority start in the order they were requested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+struct Job {
+    asset_id: AssetId,
+    priority: LoadPriority,
+    sequence: u64,
+    /// Token shared by the asset's strong handles. Once they're all dropped the job is
+    /// discarded instead of run.
+    token: Weak<()>,
+    load: BoxFuture<'static, ()>,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: Vec<Job>,
+    next_sequence: u64,
+    shut_down: bool,
+}
+
+impl Queue {
+    /// Takes the highest priority job, dropping cancelled ones.
+    fn pop(&mut self) -> Option<Job> {
+        self.jobs.retain(|job| job.token.strong_count() > 0);
+
+        let (i, _) = self.jobs.iter().enumerate()
+            .max_by_key(|(_, job)| (job.priority, std::cmp::Reverse(job.sequence)))?;
+        Some(self.jobs.swap_remove(i))
+    }
+}
+
+/// Runs asset loads on a fixed number of worker threads, highest `LoadPriority` first.
+///
+/// No async runtime is needed: each worker drives one load at a time to completion, so
+/// `AssetSource`s are free to block. Dropping the loader lets its workers finish the loads
+/// already queued before they exit.
+pub struct AssetLoader {
+    /// `None` runs loads on the calling thread.
+    queue: Option<Arc<(Mutex<Queue>, Condvar)>>,
+}
+
+impl AssetLoader {
+    pub fn new(worker_count: usize) -> Self {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+
+        for i in 0..worker_count.max(1) {
+            let queue = Arc::clone(&queue);
+            thread::Builder::new()
+                .name(format!("asset-loader-{}", i))
+                .spawn(move || Self::work(&queue))
+                .expect("Unable to spawn asset loader thread");
+        }
+
+        Self {
+            queue: Some(queue),
+        }
+    }
+
+    /// Runs every load on the calling thread as soon as it's requested, so assets are in their
+    /// pool by the time `AssetManager::get_handle` returns. Meant for tests and tools.
+    pub fn immediate() -> Self {
+        Self {
+            queue: None,
+        }
+    }
+
+    pub fn is_immediate(&self) -> bool {
+        self.queue.is_none()
+    }
+
+    /// Number of loads waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.queue.as_ref()
+            .map_or(0, |queue| queue.0.lock().unwrap().jobs.len())
+    }
+
+    pub(crate) fn spawn(&self, asset_id: AssetId, priority: LoadPriority, token: Weak<()>, load: BoxFuture<'static, ()>) {
+        let Some(queue) = &self.queue else {
+            Self::run(load);
+            return;
+        };
+
+        let (queue, ready) = &**queue;
+        let mut queue = queue.lock().unwrap();
+        let sequence = queue.next_sequence;
+        queue.next_sequence += 1;
+        queue.jobs.push(Job {
+            asset_id,
+            priority,
+            sequence,
+            token,
+            load,
+        });
+        ready.notify_one();
+    }
+
+    /// Moves a load that hasn't started yet to `priority`.
+    pub(crate) fn set_priority(&self, asset_id: AssetId, priority: LoadPriority) {
+        if let Some(queue) = &self.queue {
+            let mut queue = queue.0.lock().unwrap();
+            for job in queue.jobs.iter_mut().filter(|job| job.asset_id == asset_id) {
+                job.priority = priority;
+            }
+        }
+    }
+
+    fn work(queue: &(Mutex<Queue>, Condvar)) {
+        let (queue, ready) = queue;
+        loop {
+            let job = {
+                let mut queue = queue.lock().unwrap();
+                loop {
+                    if let Some(job) = queue.pop() {
+                        break job;
+                    }
+                    if queue.shut_down {
+                        return;
+                    }
+                    queue = ready.wait(queue).unwrap();
+                }
+            };
+
+            Self::run(job.load);
+        }
+    }
+
+    /// Keeps the worker alive if a load panics.
+    fn run(load: BoxFuture<'static, ()>) {
+        if futures::executor::block_on(AssertUnwindSafe(load).catch_unwind()).is_err() {
+            log::error!("Asset load panicked");
+        }
+    }
+}
+
+impl Default for AssetLoader {
+    /// One worker per core, leaving one for the main thread.
+    fn default() -> Self {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self::new(cores.saturating_sub(1))
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        if let Some(queue) = &self.queue {
+            queue.0.lock().unwrap().shut_down = true;
+            queue.1.notify_all();
+        }
+    }
+}
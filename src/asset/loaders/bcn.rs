@@ -0,0 +1,190 @@
+//! CPU decoders for BC1-BC5 blocks, each returning a 4x4 block of RGBA texels in row-major order.
+
+pub fn decode_bc1(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_color(block, false)
+}
+
+pub fn decode_bc2(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut texels = decode_color(&block[8..], true);
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+
+    for (i, texel) in texels.iter_mut().enumerate() {
+        texel[3] = ((alpha >> (i * 4)) & 0xf) as u8 * 17;
+    }
+
+    texels
+}
+
+pub fn decode_bc3(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut texels = decode_color(&block[8..], true);
+    let alpha = decode_channel(&block[..8]);
+
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+
+    texels
+}
+
+pub fn decode_bc4(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_channel(block).map(|r| [r, 0, 0, 255])
+}
+
+pub fn decode_bc4_snorm(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_channel_snorm(block).map(|r| [r, 0, 0, 127])
+}
+
+pub fn decode_bc5(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_channel(&block[..8]);
+    let green = decode_channel(&block[8..]);
+
+    std::array::from_fn(|i| [red[i], green[i], 0, 255])
+}
+
+pub fn decode_bc5_snorm(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_channel_snorm(&block[..8]);
+    let green = decode_channel_snorm(&block[8..]);
+
+    std::array::from_fn(|i| [red[i], green[i], 0, 127])
+}
+
+/// Decodes the 8 byte color block shared by BC1-BC3. BC2 and BC3 always use the
+/// four color palette, BC1 switches to three colors and transparent black when `c0 <= c1`.
+fn decode_color(block: &[u8], four_color: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+
+    let rgb0 = rgb565(c0);
+    let rgb1 = rgb565(c1);
+    let mix = |a: u32, b: u32, d: u32| -> [u8; 4] {
+        let channel = |i: usize| ((rgb0[i] as u32 * a + rgb1[i] as u32 * b) / d) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+
+    let palette = if four_color || c0 > c1 {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(1, 1, 2), [0, 0, 0, 0]]
+    };
+
+    std::array::from_fn(|i| palette[((indices >> (i * 2)) & 0x3) as usize])
+}
+
+fn rgb565(color: u16) -> [u8; 3] {
+    let r = ((color >> 11) & 0x1f) as u8;
+    let g = ((color >> 5) & 0x3f) as u8;
+    let b = (color & 0x1f) as u8;
+
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2]
+}
+
+/// Decodes a BC4 style single channel block, as also used for BC3 alpha and both BC5 channels.
+fn decode_channel(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+
+    let palette: [u8; 8] = std::array::from_fn(|i| match i {
+        0 => a0 as u8,
+        1 => a1 as u8,
+        i if a0 > a1 => ((a0 * (8 - i as u32) + a1 * (i as u32 - 1)) / 7) as u8,
+        6 => 0,
+        7 => 255,
+        i => ((a0 * (6 - i as u32) + a1 * (i as u32 - 1)) / 5) as u8,
+    });
+
+    let indices = channel_indices(block);
+    std::array::from_fn(|i| palette[indices[i]])
+}
+
+/// Same as `decode_channel` for signed blocks, returning each value as the bits of an `i8`.
+fn decode_channel_snorm(block: &[u8]) -> [u8; 16] {
+    let a0 = (block[0] as i8).max(-127) as i32;
+    let a1 = (block[1] as i8).max(-127) as i32;
+
+    let palette: [i8; 8] = std::array::from_fn(|i| match i {
+        0 => a0 as i8,
+        1 => a1 as i8,
+        i if a0 > a1 => ((a0 * (8 - i as i32) + a1 * (i as i32 - 1)) / 7) as i8,
+        6 => -127,
+        7 => 127,
+        i => ((a0 * (6 - i as i32) + a1 * (i as i32 - 1)) / 5) as i8,
+    });
+
+    let indices = channel_indices(block);
+    std::array::from_fn(|i| palette[indices[i]] as u8)
+}
+
+fn channel_indices(block: &[u8]) -> [usize; 16] {
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let bits = u64::from_le_bytes(bits);
+
+    std::array::from_fn(|i| ((bits >> (i * 3)) & 0x7) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Color block from pure red to pure blue, texels 0 to 3 using indices 0 to 3.
+    const RED_TO_BLUE: [u8; 8] = [0x00, 0xf8, 0x1f, 0x00, 0xe4, 0, 0, 0];
+
+    #[test]
+    fn decodes_bc1_four_color_palette() {
+        let texels = decode_bc1(&RED_TO_BLUE);
+
+        assert_eq!(texels[..4], [[255, 0, 0, 255], [0, 0, 255, 255], [170, 0, 85, 255], [85, 0, 170, 255]]);
+        assert!(texels[4..].iter().all(|&texel| texel == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn decodes_bc1_transparent_black_when_c0_is_not_greater() {
+        let texels = decode_bc1(&[0x1f, 0x00, 0x00, 0xf8, 0xe4, 0, 0, 0]);
+
+        assert_eq!(texels[..4], [[0, 0, 255, 255], [255, 0, 0, 255], [127, 0, 127, 255], [0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn decodes_bc2_explicit_alpha() {
+        let mut block = [0; 16];
+        block[0] = 0x8f;
+        block[8..].copy_from_slice(&RED_TO_BLUE);
+        let texels = decode_bc2(&block);
+
+        assert_eq!(texels[0], [255, 0, 0, 255]);
+        assert_eq!(texels[1], [0, 0, 255, 136]);
+        assert_eq!(texels[2][3], 0);
+    }
+
+    #[test]
+    fn decodes_interpolated_channels() {
+        // Texels 0 to 2 use indices 0 to 2.
+        let eight_values = [255, 0, 136, 0, 0, 0, 0, 0];
+        assert_eq!(decode_channel(&eight_values)[..4], [255, 0, 218, 255]);
+
+        // Texels 0 to 2 use indices 2, 6 and 7.
+        let six_values = [0, 255, 0b11_110_010, 0b1, 0, 0, 0, 0];
+        assert_eq!(decode_channel(&six_values)[..4], [51, 0, 255, 0]);
+    }
+
+    #[test]
+    fn decodes_bc3_alpha_and_bc5_channels() {
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&[255, 0, 136, 0, 0, 0, 0, 0]);
+        block[8..].copy_from_slice(&RED_TO_BLUE);
+        let texels = decode_bc3(&block);
+        assert_eq!(texels[2], [170, 0, 85, 218]);
+
+        let block = [255, 0, 136, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0];
+        assert_eq!(decode_bc5(&block)[..3], [[255, 0, 0, 255], [0, 0, 0, 255], [218, 0, 0, 255]]);
+    }
+
+    #[test]
+    fn decodes_signed_channels() {
+        // -128 decodes the same as -127, then texels 0 to 2 use indices 0, 6 and 7.
+        let block = [0x80, 0x7f, 0b11_110_000, 0b1, 0, 0, 0, 0];
+        let texels = decode_bc4_snorm(&block);
+
+        assert_eq!(texels[..3], [[0x81, 0, 0, 127], [0x81, 0, 0, 127], [0x7f, 0, 0, 127]]);
+    }
+}
@@ -0,0 +1,546 @@
+//! CPU decoders for BC6H and BC7 (BPTC) blocks, each returning a 4x4 block of texels in
+//! row-major order. BC7 decodes to RGBA8, BC6H to RGBA16F as little-endian half float bits.
+
+/// Subset of each texel for the 2 subset partitions, one bit per texel.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Subset of each texel for the 3 subset partitions.
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+/// Texel whose index drops its top bit, for the second subset of each 2 subset partition.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Anchor texels of the second and third subset of each 3 subset partition.
+const ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Reads a block's fields from its least significant bit up.
+struct Bits {
+    bits: u128,
+    position: u32,
+}
+
+impl Bits {
+    fn new(block: &[u8]) -> Self {
+        Self {
+            bits: u128::from_le_bytes(block[..16].try_into().unwrap()),
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+fn subset(subsets: usize, partition: usize, texel: usize) -> usize {
+    match subsets {
+        1 => 0,
+        2 => (PARTITIONS_2[partition] >> texel) as usize & 1,
+        _ => PARTITIONS_3[partition][texel] as usize,
+    }
+}
+
+/// Anchor texels store their index without its top bit, which is always 0.
+fn is_anchor(subsets: usize, partition: usize, texel: usize) -> bool {
+    texel == 0 || match subsets {
+        1 => false,
+        2 => ANCHORS_2[partition] as usize == texel,
+        _ => ANCHORS_3[partition].contains(&(texel as u8)),
+    }
+}
+
+fn weight(index_bits: u32, index: u32) -> u32 {
+    match index_bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    /// One P-bit per endpoint, appended to every channel.
+    endpoint_p_bits: bool,
+    /// One P-bit per subset, shared by both its endpoints.
+    shared_p_bits: bool,
+    index_bits: u32,
+    /// Separate alpha indices, swapped with the color indices by the index selection bit.
+    secondary_index_bits: u32,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+pub fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    // The mode is the number of zero bits before the first set one. Reserved blocks are
+    // transparent black.
+    let mode = block[0].trailing_zeros();
+    let Some(mode_info) = BC7_MODES.get(mode as usize) else {
+        return [[0; 4]; 16];
+    };
+
+    let mut bits = Bits::new(block);
+    bits.read(mode + 1);
+
+    let partition = bits.read(mode_info.partition_bits) as usize;
+    let rotation = bits.read(mode_info.rotation_bits);
+    let index_selection = bits.read(mode_info.index_selection_bits);
+
+    let endpoint_count = mode_info.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(mode_info.color_bits);
+        }
+    }
+    for endpoint in &mut endpoints[..endpoint_count] {
+        endpoint[3] = bits.read(mode_info.alpha_bits);
+    }
+
+    let mut color_bits = mode_info.color_bits;
+    let mut alpha_bits = mode_info.alpha_bits;
+    if mode_info.endpoint_p_bits || mode_info.shared_p_bits {
+        let mut p_bit = 0;
+        for (i, endpoint) in endpoints[..endpoint_count].iter_mut().enumerate() {
+            if mode_info.endpoint_p_bits || i % 2 == 0 {
+                p_bit = bits.read(1);
+            }
+            endpoint.iter_mut().for_each(|channel| *channel = *channel << 1 | p_bit);
+        }
+
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    for endpoint in &mut endpoints[..endpoint_count] {
+        for channel in &mut endpoint[..3] {
+            *channel = expand(*channel, color_bits);
+        }
+        endpoint[3] = match alpha_bits {
+            0 => 255,
+            alpha_bits => expand(endpoint[3], alpha_bits),
+        };
+    }
+
+    let mut indices = [0; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode_info.subsets, partition, texel);
+        *index = bits.read(mode_info.index_bits - anchor as u32);
+    }
+
+    let mut secondary_indices = [0; 16];
+    if mode_info.secondary_index_bits > 0 {
+        for (texel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode_info.secondary_index_bits - (texel == 0) as u32);
+        }
+    }
+
+    let ((color_indices, color_index_bits), (alpha_indices, alpha_index_bits)) = match (mode_info.secondary_index_bits, index_selection) {
+        (0, _) => ((indices, mode_info.index_bits), (indices, mode_info.index_bits)),
+        (_, 0) => ((indices, mode_info.index_bits), (secondary_indices, mode_info.secondary_index_bits)),
+        _ => ((secondary_indices, mode_info.secondary_index_bits), (indices, mode_info.index_bits)),
+    };
+
+    std::array::from_fn(|texel| {
+        let subset = subset(mode_info.subsets, partition, texel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let color_weight = weight(color_index_bits, color_indices[texel]);
+        let alpha_weight = weight(alpha_index_bits, alpha_indices[texel]);
+        let mut texel: [u8; 4] = std::array::from_fn(|channel| {
+            let weight = if channel == 3 { alpha_weight } else { color_weight };
+            (((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6) as u8
+        });
+
+        if rotation > 0 {
+            texel.swap(rotation as usize - 1, 3);
+        }
+        texel
+    })
+}
+
+/// Widens a `bits` wide value to 8 bits by repeating its top bits.
+fn expand(value: u32, bits: u32) -> u32 {
+    let value = value << (8 - bits);
+    value | value >> bits
+}
+
+/// Endpoint components in the order `Bc6hMode::layout` refers to them: red, green and blue
+/// of the first subset's endpoints, then of the second's.
+const R0: u8 = 0;
+const G0: u8 = 1;
+const B0: u8 = 2;
+const R1: u8 = 3;
+const G1: u8 = 4;
+const B1: u8 = 5;
+const R2: u8 = 6;
+const G2: u8 = 7;
+const B2: u8 = 8;
+const R3: u8 = 9;
+const G3: u8 = 10;
+const B3: u8 = 11;
+
+struct Bc6hMode {
+    /// Value of the mode bits, 2 bits for the first two modes and 5 for the rest.
+    mode: u32,
+    subsets: usize,
+    endpoint_bits: u32,
+    /// Bits of each channel of the other endpoints, which are deltas from the first if `transformed`.
+    delta_bits: [u32; 3],
+    transformed: bool,
+    /// Endpoint bits in the order they're stored: component, first bit, last bit. Bits are
+    /// read from first to last, which counts down in the few fields stored reversed.
+    layout: &'static [(u8, u8, u8)],
+}
+
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode { mode: 0, subsets: 2, endpoint_bits: 10, delta_bits: [5, 5, 5], transformed: true, layout: &[
+        (G2, 4, 4), (B2, 4, 4), (B3, 4, 4), (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 4), (G3, 4, 4),
+        (G2, 0, 3), (G1, 0, 4), (B3, 0, 0), (G3, 0, 3), (B1, 0, 4), (B3, 1, 1), (B2, 0, 3), (R2, 0, 4),
+        (B3, 2, 2), (R3, 0, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 1, subsets: 2, endpoint_bits: 7, delta_bits: [6, 6, 6], transformed: true, layout: &[
+        (G2, 5, 5), (G3, 4, 4), (G3, 5, 5), (R0, 0, 6), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 0, 6),
+        (B2, 5, 5), (B3, 2, 2), (G2, 4, 4), (B0, 0, 6), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 0, 5),
+        (G2, 0, 3), (G1, 0, 5), (G3, 0, 3), (B1, 0, 5), (B2, 0, 3), (R2, 0, 5), (R3, 0, 5),
+    ] },
+    Bc6hMode { mode: 2, subsets: 2, endpoint_bits: 11, delta_bits: [5, 4, 4], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 4), (R0, 10, 10), (G2, 0, 3), (G1, 0, 3), (G0, 10, 10),
+        (B3, 0, 0), (G3, 0, 3), (B1, 0, 3), (B0, 10, 10), (B3, 1, 1), (B2, 0, 3), (R2, 0, 4), (B3, 2, 2),
+        (R3, 0, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 6, subsets: 2, endpoint_bits: 11, delta_bits: [4, 5, 4], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 3), (R0, 10, 10), (G3, 4, 4), (G2, 0, 3), (G1, 0, 4),
+        (G0, 10, 10), (G3, 0, 3), (B1, 0, 3), (B0, 10, 10), (B3, 1, 1), (B2, 0, 3), (R2, 0, 3), (B3, 0, 0),
+        (B3, 2, 2), (R3, 0, 3), (G2, 4, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 10, subsets: 2, endpoint_bits: 11, delta_bits: [4, 4, 5], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 3), (R0, 10, 10), (B2, 4, 4), (G2, 0, 3), (G1, 0, 3),
+        (G0, 10, 10), (B3, 0, 0), (G3, 0, 3), (B1, 0, 4), (B0, 10, 10), (B2, 0, 3), (R2, 0, 3), (B3, 1, 1),
+        (B3, 2, 2), (R3, 0, 3), (B3, 4, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 14, subsets: 2, endpoint_bits: 9, delta_bits: [5, 5, 5], transformed: true, layout: &[
+        (R0, 0, 8), (B2, 4, 4), (G0, 0, 8), (G2, 4, 4), (B0, 0, 8), (B3, 4, 4), (R1, 0, 4), (G3, 4, 4),
+        (G2, 0, 3), (G1, 0, 4), (B3, 0, 0), (G3, 0, 3), (B1, 0, 4), (B3, 1, 1), (B2, 0, 3), (R2, 0, 4),
+        (B3, 2, 2), (R3, 0, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 18, subsets: 2, endpoint_bits: 8, delta_bits: [6, 5, 5], transformed: true, layout: &[
+        (R0, 0, 7), (G3, 4, 4), (B2, 4, 4), (G0, 0, 7), (B3, 2, 2), (G2, 4, 4), (B0, 0, 7), (B3, 3, 3),
+        (B3, 4, 4), (R1, 0, 5), (G2, 0, 3), (G1, 0, 4), (B3, 0, 0), (G3, 0, 3), (B1, 0, 4), (B3, 1, 1),
+        (B2, 0, 3), (R2, 0, 5), (R3, 0, 5),
+    ] },
+    Bc6hMode { mode: 22, subsets: 2, endpoint_bits: 8, delta_bits: [5, 6, 5], transformed: true, layout: &[
+        (R0, 0, 7), (B3, 0, 0), (B2, 4, 4), (G0, 0, 7), (G2, 5, 5), (G2, 4, 4), (B0, 0, 7), (G3, 5, 5),
+        (B3, 4, 4), (R1, 0, 4), (G3, 4, 4), (G2, 0, 3), (G1, 0, 5), (G3, 0, 3), (B1, 0, 4), (B3, 1, 1),
+        (B2, 0, 3), (R2, 0, 4), (B3, 2, 2), (R3, 0, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 26, subsets: 2, endpoint_bits: 8, delta_bits: [5, 5, 6], transformed: true, layout: &[
+        (R0, 0, 7), (B3, 1, 1), (B2, 4, 4), (G0, 0, 7), (B2, 5, 5), (G2, 4, 4), (B0, 0, 7), (B3, 5, 5),
+        (B3, 4, 4), (R1, 0, 4), (G3, 4, 4), (G2, 0, 3), (G1, 0, 4), (B3, 0, 0), (G3, 0, 3), (B1, 0, 5),
+        (B2, 0, 3), (R2, 0, 4), (B3, 2, 2), (R3, 0, 4), (B3, 3, 3),
+    ] },
+    Bc6hMode { mode: 30, subsets: 2, endpoint_bits: 6, delta_bits: [6, 6, 6], transformed: false, layout: &[
+        (R0, 0, 5), (G3, 4, 4), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 0, 5), (G2, 5, 5), (B2, 5, 5),
+        (B3, 2, 2), (G2, 4, 4), (B0, 0, 5), (G3, 5, 5), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 0, 5),
+        (G2, 0, 3), (G1, 0, 5), (G3, 0, 3), (B1, 0, 5), (B2, 0, 3), (R2, 0, 5), (R3, 0, 5),
+    ] },
+    Bc6hMode { mode: 3, subsets: 1, endpoint_bits: 10, delta_bits: [10, 10, 10], transformed: false, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 9), (G1, 0, 9), (B1, 0, 9),
+    ] },
+    Bc6hMode { mode: 7, subsets: 1, endpoint_bits: 11, delta_bits: [9, 9, 9], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 8), (R0, 10, 10), (G1, 0, 8), (G0, 10, 10), (B1, 0, 8),
+        (B0, 10, 10),
+    ] },
+    Bc6hMode { mode: 11, subsets: 1, endpoint_bits: 12, delta_bits: [8, 8, 8], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 7), (R0, 11, 10), (G1, 0, 7), (G0, 11, 10), (B1, 0, 7),
+        (B0, 11, 10),
+    ] },
+    Bc6hMode { mode: 15, subsets: 1, endpoint_bits: 16, delta_bits: [4, 4, 4], transformed: true, layout: &[
+        (R0, 0, 9), (G0, 0, 9), (B0, 0, 9), (R1, 0, 3), (R0, 15, 10), (G1, 0, 3), (G0, 15, 10), (B1, 0, 3),
+        (B0, 15, 10),
+    ] },
+];
+
+/// Half float 1.0, the alpha of every BC6H texel.
+const HALF_ONE: u16 = 0x3c00;
+
+pub fn decode_bc6h_unsigned(block: &[u8]) -> [[u8; 8]; 16] {
+    decode_bc6h(block, false)
+}
+
+pub fn decode_bc6h_signed(block: &[u8]) -> [[u8; 8]; 16] {
+    decode_bc6h(block, true)
+}
+
+fn decode_bc6h(block: &[u8], signed: bool) -> [[u8; 8]; 16] {
+    let mut bits = Bits::new(block);
+    let mut mode = bits.read(2);
+    if mode > 1 {
+        mode |= bits.read(3) << 2;
+    }
+
+    // Reserved modes decode to black.
+    let Some(mode_info) = BC6H_MODES.iter().find(|mode_info| mode_info.mode == mode) else {
+        return [half_texel([0; 3]); 16];
+    };
+
+    let mut components = [0i32; 12];
+    for &(component, first, last) in mode_info.layout {
+        for step in 0..=first.abs_diff(last) {
+            let position = if first <= last { first + step } else { first - step };
+            components[component as usize] |= (bits.read(1) as i32) << position;
+        }
+    }
+
+    let partition = match mode_info.subsets {
+        2 => bits.read(5) as usize,
+        _ => 0,
+    };
+
+    // Transformed modes store the other endpoints as deltas from the first one.
+    let endpoint_bits = mode_info.endpoint_bits;
+    let extend = |value: i32| if signed { sign_extend(value, endpoint_bits) } else { value };
+    let mut endpoints = [[0i32; 3]; 4];
+    for (i, endpoint) in endpoints[..mode_info.subsets * 2].iter_mut().enumerate() {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let component = components[i * 3 + channel];
+            let component = if i > 0 && mode_info.transformed {
+                let delta = sign_extend(component, mode_info.delta_bits[channel]);
+                extend((extend(components[channel]) + delta) & ((1 << endpoint_bits) - 1))
+            } else {
+                extend(component)
+            };
+
+            *value = unquantize(component, endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if mode_info.subsets == 2 { 3 } else { 4 };
+    std::array::from_fn(|texel| {
+        let anchor = is_anchor(mode_info.subsets, partition, texel);
+        let weight = weight(index_bits, bits.read(index_bits - anchor as u32)) as i32;
+
+        let subset = subset(mode_info.subsets, partition, texel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        half_texel(std::array::from_fn(|channel| {
+            let value = ((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6;
+            finish_unquantize(value, signed)
+        }))
+    })
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    (value << (32 - bits)) >> (32 - bits)
+}
+
+/// Scales an endpoint to the full 16 bit range the interpolation runs in.
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        return match value {
+            _ if bits >= 15 => value,
+            0 => 0,
+            _ if value == (1 << bits) - 1 => 0xffff,
+            _ => ((value << 16) + 0x8000) >> bits,
+        };
+    }
+
+    if bits >= 16 {
+        return value;
+    }
+
+    let magnitude = match value.abs() {
+        0 => 0,
+        magnitude if magnitude >= (1 << (bits - 1)) - 1 => 0x7fff,
+        magnitude => ((magnitude << 15) + 0x4000) >> (bits - 1),
+    };
+    if value < 0 { -magnitude } else { magnitude }
+}
+
+/// Scales an interpolated value to the bits of a half float.
+fn finish_unquantize(value: i32, signed: bool) -> u16 {
+    match (signed, value < 0) {
+        (false, _) => ((value * 31) >> 6) as u16,
+        (true, false) => ((value * 31) >> 5) as u16,
+        (true, true) => 0x8000 | ((-value * 31) >> 5) as u16,
+    }
+}
+
+fn half_texel(rgb: [u16; 3]) -> [u8; 8] {
+    let mut texel = [0; 8];
+    for (bytes, channel) in texel.chunks_exact_mut(2).zip([rgb[0], rgb[1], rgb[2], HALF_ONE]) {
+        bytes.copy_from_slice(&channel.to_le_bytes());
+    }
+    texel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_fall_in_their_subset() {
+        for partition in 0..64 {
+            assert_eq!(subset(2, partition, ANCHORS_2[partition] as usize), 1);
+            assert_eq!(subset(3, partition, ANCHORS_3[partition][0] as usize), 1);
+            assert_eq!(subset(3, partition, ANCHORS_3[partition][1] as usize), 2);
+            assert_eq!(subset(2, partition, 0), 0);
+            assert_eq!(subset(3, partition, 0), 0);
+        }
+    }
+
+    #[test]
+    fn bc6h_modes_fill_their_header() {
+        for mode in &BC6H_MODES {
+            let bits: u32 = mode.layout.iter().map(|&(_, first, last)| first.abs_diff(last) as u32 + 1).sum();
+            let mode_bits = if mode.mode < 2 { 2 } else { 5 };
+            let partition_bits = if mode.subsets == 2 { 5 } else { 0 };
+            let index_bits = if mode.subsets == 2 { 46 } else { 63 };
+            assert_eq!(mode_bits + bits + partition_bits + index_bits, 128, "mode {}", mode.mode);
+        }
+    }
+
+    #[test]
+    fn decodes_bc7_mode_6() {
+        // White first endpoint from 7-bit 0x7f plus a set P-bit, black second endpoint, and
+        // texel 1 at the last index.
+        let mut bits = 1u128 << 6;
+        for channel in 0..4 {
+            bits |= 0x7f << (7 + channel * 14);
+        }
+        bits |= 1 << 63;
+        bits |= 0xf << 68;
+
+        let texels = decode_bc7(&bits.to_le_bytes());
+        assert_eq!(texels[0], [255; 4]);
+        assert_eq!(texels[1], [0; 4]);
+        assert!(texels[2..].iter().all(|&texel| texel == [255; 4]));
+    }
+
+    #[test]
+    fn reserved_bc7_mode_decodes_to_transparent_black() {
+        assert_eq!(decode_bc7(&[0; 16]), [[0; 4]; 16]);
+    }
+
+    #[test]
+    fn decodes_bc6h_mode_3() {
+        // Both endpoints of mode 3 (two mode bits of 1, three of 0) at their 10-bit maximum.
+        let mut bits = 0b00011u128;
+        for component in 0..6 {
+            bits |= 0x3ff << (5 + component * 10);
+        }
+
+        let max = 0x7bffu16.to_le_bytes();
+        let one = HALF_ONE.to_le_bytes();
+        let texel = [max[0], max[1], max[0], max[1], max[0], max[1], one[0], one[1]];
+        assert_eq!(decode_bc6h_unsigned(&bits.to_le_bytes()), [texel; 16]);
+    }
+}
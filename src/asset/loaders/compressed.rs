@@ -0,0 +1,243 @@
+use std::io::Read;
+
+use anyhow::{bail, Result};
+
+use crate::asset::sources::archive::read_u32;
+
+use super::{bcn, bptc, etc2};
+
+const MAGIC: &[u8; 4] = b"RTEX";
+const VERSION: u32 = 1;
+
+/// Formats `to_bytes` can store, by their index in the file. Only append to this list.
+const FORMATS: [wgpu::TextureFormat; 5] = [
+    wgpu::TextureFormat::Rgba8Unorm,
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+    wgpu::TextureFormat::Rgba8Snorm,
+    wgpu::TextureFormat::Rgba16Float,
+    wgpu::TextureFormat::Rgba32Float,
+];
+
+/// Decodes one block to its 4x4 texels in row-major order, each `N` bytes.
+type DecodeBlock<const N: usize> = fn(&[u8]) -> [[u8; N]; 16];
+
+/// Texture data that is uploaded as-is, with every mip level already encoded in `format`.
+#[derive(Clone)]
+pub struct CompressedImage {
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Mip levels, largest first, each tightly packed in rows of blocks.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    /// Byte size of mip `level` of a `width` x `height` image in `format`.
+    pub fn level_size(format: wgpu::TextureFormat, width: u32, height: u32, level: u32) -> usize {
+        let (blocks_x, blocks_y) = Self::level_blocks(format, width, height, level);
+        let block_size = format.block_size(None).unwrap_or(4);
+
+        (blocks_x * blocks_y * block_size) as usize
+    }
+
+    /// Number of blocks across and down mip `level`.
+    pub fn level_blocks(format: wgpu::TextureFormat, width: u32, height: u32, level: u32) -> (u32, u32) {
+        let (block_width, block_height) = format.block_dimensions();
+        let width = (width >> level).max(1);
+        let height = (height >> level).max(1);
+
+        (width.div_ceil(block_width), height.div_ceil(block_height))
+    }
+
+    /// Drops mip levels larger than `max_size` in either dimension, keeping at least the smallest.
+    pub fn limit_size(&self, max_size: u32) -> std::borrow::Cow<'_, CompressedImage> {
+        let skip = (0..self.levels.len().saturating_sub(1))
+            .take_while(|&level| max_size != 0 && (self.width >> level).max(self.height >> level) > max_size)
+            .count();
+
+        if skip == 0 {
+            return std::borrow::Cow::Borrowed(self);
+        }
+
+        std::borrow::Cow::Owned(CompressedImage {
+            format: self.format,
+            width: (self.width >> skip).max(1),
+            height: (self.height >> skip).max(1),
+            levels: self.levels[skip..].to_vec(),
+        })
+    }
+
+    /// Decodes every level for devices without support for `format`, to 8-bit RGBA or, for
+    /// BC6H, half float RGBA. ASTC has no CPU decoder, so ASTC textures fail to load on devices
+    /// that can't sample them and show the error placeholder instead.
+    pub fn decompress(&self) -> Result<CompressedImage> {
+        use wgpu::TextureFormat as F;
+
+        let decode_block: DecodeBlock<4> = match self.format {
+            F::Bc1RgbaUnorm | F::Bc1RgbaUnormSrgb => bcn::decode_bc1,
+            F::Bc2RgbaUnorm | F::Bc2RgbaUnormSrgb => bcn::decode_bc2,
+            F::Bc3RgbaUnorm | F::Bc3RgbaUnormSrgb => bcn::decode_bc3,
+            F::Bc4RUnorm => bcn::decode_bc4,
+            F::Bc4RSnorm => bcn::decode_bc4_snorm,
+            F::Bc5RgUnorm => bcn::decode_bc5,
+            F::Bc5RgSnorm => bcn::decode_bc5_snorm,
+            F::Bc6hRgbUfloat => return Ok(self.decode_blocks(F::Rgba16Float, bptc::decode_bc6h_unsigned)),
+            F::Bc6hRgbFloat => return Ok(self.decode_blocks(F::Rgba16Float, bptc::decode_bc6h_signed)),
+            F::Bc7RgbaUnorm | F::Bc7RgbaUnormSrgb => bptc::decode_bc7,
+            F::Etc2Rgb8Unorm | F::Etc2Rgb8UnormSrgb => etc2::decode_rgb8,
+            F::Etc2Rgb8A1Unorm | F::Etc2Rgb8A1UnormSrgb => etc2::decode_rgb8a1,
+            F::Etc2Rgba8Unorm | F::Etc2Rgba8UnormSrgb => etc2::decode_rgba8,
+            F::EacR11Unorm => etc2::decode_r11,
+            F::EacR11Snorm => etc2::decode_r11_snorm,
+            F::EacRg11Unorm => etc2::decode_rg11,
+            F::EacRg11Snorm => etc2::decode_rg11_snorm,
+            format => bail!("{:?} is not supported by this device and has no CPU decoder", format),
+        };
+
+        let format = match self.format {
+            F::Bc4RSnorm | F::Bc5RgSnorm | F::EacR11Snorm | F::EacRg11Snorm => F::Rgba8Snorm,
+            format if format.is_srgb() => F::Rgba8UnormSrgb,
+            _ => F::Rgba8Unorm,
+        };
+
+        Ok(self.decode_blocks(format, decode_block))
+    }
+
+    fn decode_blocks<const N: usize>(&self, format: wgpu::TextureFormat, decode_block: DecodeBlock<N>) -> CompressedImage {
+        let block_size = self.format.block_size(None).unwrap_or(16) as usize;
+
+        let mut levels = Vec::with_capacity(self.levels.len());
+        for (level, data) in self.levels.iter().enumerate() {
+            let width = (self.width >> level).max(1) as usize;
+            let height = (self.height >> level).max(1) as usize;
+            let blocks_x = width.div_ceil(4);
+
+            let mut pixels = vec![0; width * height * N];
+            for (i, block) in data.chunks_exact(block_size).enumerate() {
+                let (block_x, block_y) = (i % blocks_x * 4, i / blocks_x * 4);
+                let texels = decode_block(block);
+
+                for y in 0..4.min(height.saturating_sub(block_y)) {
+                    for x in 0..4.min(width - block_x) {
+                        let offset = ((block_y + y) * width + block_x + x) * N;
+                        pixels[offset..offset + N].copy_from_slice(&texels[y * 4 + x]);
+                    }
+                }
+            }
+
+            levels.push(pixels);
+        }
+
+        CompressedImage {
+            format,
+            width: self.width,
+            height: self.height,
+            levels,
+        }
+    }
+
+    /// Serializes the image for the asset cache. Only decoded formats, see `FORMATS`, are
+    /// supported since compressed files are already uploaded without decoding.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let Some(format) = FORMATS.iter().position(|&format| format == self.format) else {
+            bail!("{:?} textures can't be cooked", self.format);
+        };
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+        for value in [format as u32, self.width, self.height, self.levels.len() as u32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for level in &self.levels {
+            bytes.extend_from_slice(level);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = bytes;
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a cooked reclipse texture");
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            bail!("unsupported cooked texture version {}", version);
+        }
+
+        let format = read_u32(&mut reader)? as usize;
+        let Some(&format) = FORMATS.get(format) else {
+            bail!("unknown texture format {}", format);
+        };
+        let width = read_u32(&mut reader)?;
+        let height = read_u32(&mut reader)?;
+
+        let mut levels = Vec::new();
+        for level in 0..read_u32(&mut reader)? {
+            let mut data = vec![0; Self::level_size(format, width, height, level)];
+            reader.read_exact(&mut data)?;
+            levels.push(data);
+        }
+
+        Ok(Self {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 5x3 image with its full mip chain, every byte set to its level.
+    fn image(format: wgpu::TextureFormat) -> CompressedImage {
+        let levels = (0..3)
+            .map(|level| vec![level as u8; CompressedImage::level_size(format, 5, 3, level)])
+            .collect();
+
+        CompressedImage {
+            format,
+            width: 5,
+            height: 3,
+            levels,
+        }
+    }
+
+    #[test]
+    fn computes_level_sizes() {
+        assert_eq!(CompressedImage::level_size(wgpu::TextureFormat::Rgba8Unorm, 5, 3, 1), 2 * 4);
+        assert_eq!(CompressedImage::level_size(wgpu::TextureFormat::Rgba16Float, 5, 3, 2), 8);
+        assert_eq!(CompressedImage::level_size(wgpu::TextureFormat::Bc1RgbaUnorm, 5, 3, 0), 2 * 8);
+        assert_eq!(CompressedImage::level_size(wgpu::TextureFormat::Bc7RgbaUnorm, 5, 3, 2), 16);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        for format in FORMATS {
+            let image = image(format);
+            let loaded = CompressedImage::from_bytes(&image.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(loaded.format, format);
+            assert_eq!((loaded.width, loaded.height), (5, 3));
+            assert_eq!(loaded.levels, image.levels);
+        }
+    }
+
+    #[test]
+    fn rejects_compressed_formats_and_invalid_bytes() {
+        assert!(image(wgpu::TextureFormat::Bc1RgbaUnorm).to_bytes().is_err());
+
+        let bytes = image(wgpu::TextureFormat::Rgba8Unorm).to_bytes().unwrap();
+        assert!(CompressedImage::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(CompressedImage::from_bytes(&[b"RMSH", &bytes[4..]].concat()).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, bail, Result};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use wgpu::TextureFormat;
+
+use super::compressed::CompressedImage;
+
+/// Reads a 2D `.dds` texture and all of its mip levels.
+///
+/// Legacy DX9 headers can't mark data as sRGB, so `srgb` decides how those are interpreted.
+pub fn parse(bytes: &[u8], file_path: &str, srgb: bool) -> Result<CompressedImage> {
+    let dds = Dds::read(bytes)
+        .map_err(|e| anyhow!("{}: {}", file_path, e))?;
+
+    if dds.get_depth() > 1 || dds.get_num_array_layers() > 1 {
+        bail!("{}: only single 2D textures are supported", file_path);
+    }
+
+    let format = if let Some(header10) = &dds.header10 {
+        dxgi_format(header10.dxgi_format)
+            .ok_or_else(|| anyhow!("{}: unsupported format {:?}", file_path, header10.dxgi_format))?
+    } else {
+        let format = dds.get_d3d_format()
+            .and_then(d3d_format)
+            .or_else(|| dds.get_dxgi_format().and_then(dxgi_format))
+            .ok_or_else(|| anyhow!("{}: unsupported pixel format", file_path))?;
+
+        if srgb { format.add_srgb_suffix() } else { format.remove_srgb_suffix() }
+    };
+
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let mut data = dds.data.as_slice();
+    let mut levels = Vec::new();
+    for level in 0..dds.get_num_mipmap_levels().max(1) {
+        let size = CompressedImage::level_size(format, width, height, level);
+        if data.len() < size {
+            bail!("{}: mip level {} is truncated", file_path, level);
+        }
+
+        let (level, rest) = data.split_at(size);
+        levels.push(level.to_vec());
+        data = rest;
+    }
+
+    Ok(CompressedImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::B8G8R8A8_UNorm => TextureFormat::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::R16G16B16A16_Float => TextureFormat::Rgba16Float,
+        DxgiFormat::R32G32B32A32_Float => TextureFormat::Rgba32Float,
+        DxgiFormat::BC1_UNorm => TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm => TextureFormat::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC4_SNorm => TextureFormat::Bc4RSnorm,
+        DxgiFormat::BC5_UNorm => TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC5_SNorm => TextureFormat::Bc5RgSnorm,
+        DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hRgbUfloat,
+        DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hRgbFloat,
+        DxgiFormat::BC7_UNorm => TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: D3DFormat) -> Option<TextureFormat> {
+    Some(match format {
+        D3DFormat::A8B8G8R8 => TextureFormat::Rgba8Unorm,
+        D3DFormat::A8R8G8B8 => TextureFormat::Bgra8Unorm,
+        D3DFormat::DXT1 => TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT2 | D3DFormat::DXT3 => TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT4 | D3DFormat::DXT5 => TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
@@ -0,0 +1,354 @@
+//! CPU decoders for ETC2 and EAC blocks, each returning a 4x4 block of RGBA texels in row-major order.
+//!
+//! Blocks are stored big-endian and index their texels in column-major order.
+
+const MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8], [5, 17], [9, 29], [13, 42], [18, 60], [24, 80], [33, 106], [47, 183],
+];
+
+const DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+pub fn decode_rgb8(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_color(read_bits(block), false)
+}
+
+pub fn decode_rgb8a1(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_color(read_bits(block), true)
+}
+
+pub fn decode_rgba8(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut texels = decode_color(read_bits(&block[8..]), false);
+    let alpha = decode_eac_alpha(read_bits(&block[..8]));
+
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+
+    texels
+}
+
+pub fn decode_r11(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_eac(read_bits(block)).map(|r| [(r >> 3) as u8, 0, 0, 255])
+}
+
+pub fn decode_rg11(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_eac(read_bits(&block[..8]));
+    let green = decode_eac(read_bits(&block[8..]));
+
+    std::array::from_fn(|i| [(red[i] >> 3) as u8, (green[i] >> 3) as u8, 0, 255])
+}
+
+pub fn decode_r11_snorm(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_eac_snorm(read_bits(block)).map(|r| [snorm8(r), 0, 0, 127])
+}
+
+pub fn decode_rg11_snorm(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_eac_snorm(read_bits(&block[..8]));
+    let green = decode_eac_snorm(read_bits(&block[8..]));
+
+    std::array::from_fn(|i| [snorm8(red[i]), snorm8(green[i]), 0, 127])
+}
+
+/// Narrows a signed 11-bit value to the bits of an `i8`.
+fn snorm8(value: i32) -> u8 {
+    (value >> 3).max(-127) as i8 as u8
+}
+
+fn read_bits(block: &[u8]) -> u64 {
+    u64::from_be_bytes(block[..8].try_into().unwrap())
+}
+
+fn bits(block: u64, high: u32, count: u32) -> i32 {
+    ((block >> (high + 1 - count)) & ((1 << count) - 1)) as i32
+}
+
+fn extend(value: i32, count: u32) -> i32 {
+    (value << (8 - count)) | (value >> (2 * count - 8))
+}
+
+/// 2-bit palette index of texel `i` (column-major) from the low 32 bits of the block.
+fn texel_index(block: u64, i: usize) -> usize {
+    let msb = (block >> (i + 16)) & 1;
+    let lsb = (block >> i) & 1;
+
+    (msb << 1 | lsb) as usize
+}
+
+fn clamp_rgb(rgb: [i32; 3]) -> [u8; 4] {
+    [rgb[0].clamp(0, 255) as u8, rgb[1].clamp(0, 255) as u8, rgb[2].clamp(0, 255) as u8, 255]
+}
+
+/// Decodes an ETC2 color block. With `punchthrough` bit 33 is the opaque flag instead of
+/// the differential flag, and non-opaque blocks use palette index 2 for transparent black.
+fn decode_color(block: u64, punchthrough: bool) -> [[u8; 4]; 16] {
+    let differential = punchthrough || block & (1 << 33) != 0;
+    let opaque = !punchthrough || block & (1 << 33) != 0;
+
+    let texels = if !differential {
+        let base = [
+            [extend(bits(block, 63, 4), 4), extend(bits(block, 55, 4), 4), extend(bits(block, 47, 4), 4)],
+            [extend(bits(block, 59, 4), 4), extend(bits(block, 51, 4), 4), extend(bits(block, 43, 4), 4)],
+        ];
+        decode_subblocks(block, base, true)
+    } else {
+        let r = bits(block, 63, 5);
+        let g = bits(block, 55, 5);
+        let b = bits(block, 47, 5);
+        let dr = (bits(block, 58, 3) << 29) >> 29;
+        let dg = (bits(block, 50, 3) << 29) >> 29;
+        let db = (bits(block, 42, 3) << 29) >> 29;
+
+        if !(0..32).contains(&(r + dr)) {
+            decode_t(block, opaque)
+        } else if !(0..32).contains(&(g + dg)) {
+            decode_h(block, opaque)
+        } else if !(0..32).contains(&(b + db)) {
+            decode_planar(block)
+        } else {
+            let base = [
+                [extend(r, 5), extend(g, 5), extend(b, 5)],
+                [extend(r + dr, 5), extend(g + dg, 5), extend(b + db, 5)],
+            ];
+            decode_subblocks(block, base, opaque)
+        }
+    };
+
+    transpose(texels)
+}
+
+fn decode_subblocks(block: u64, base: [[i32; 3]; 2], opaque: bool) -> [[u8; 4]; 16] {
+    let flip = block & (1 << 32) != 0;
+    let tables = [bits(block, 39, 3) as usize, bits(block, 36, 3) as usize];
+
+    std::array::from_fn(|i| {
+        let (x, y) = (i / 4, i % 4);
+        let subblock = if flip { (y >= 2) as usize } else { (x >= 2) as usize };
+        let [a, b] = MODIFIERS[tables[subblock]];
+
+        let modifier = match texel_index(block, i) {
+            0 if opaque => a,
+            0 => 0,
+            1 => b,
+            2 if opaque => -a,
+            2 => return [0, 0, 0, 0],
+            _ => -b,
+        };
+
+        let rgb = base[subblock];
+        clamp_rgb([rgb[0] + modifier, rgb[1] + modifier, rgb[2] + modifier])
+    })
+}
+
+fn decode_t(block: u64, opaque: bool) -> [[u8; 4]; 16] {
+    let c0 = [
+        extend(bits(block, 60, 2) << 2 | bits(block, 57, 2), 4),
+        extend(bits(block, 55, 4), 4),
+        extend(bits(block, 51, 4), 4),
+    ];
+    let c1 = [extend(bits(block, 47, 4), 4), extend(bits(block, 43, 4), 4), extend(bits(block, 39, 4), 4)];
+    let d = DISTANCES[(bits(block, 35, 2) << 1 | bits(block, 32, 1)) as usize];
+
+    let palette = [
+        clamp_rgb(c0),
+        clamp_rgb([c1[0] + d, c1[1] + d, c1[2] + d]),
+        clamp_rgb(c1),
+        clamp_rgb([c1[0] - d, c1[1] - d, c1[2] - d]),
+    ];
+
+    decode_paint(block, palette, opaque)
+}
+
+fn decode_h(block: u64, opaque: bool) -> [[u8; 4]; 16] {
+    let c0 = [
+        bits(block, 62, 4),
+        bits(block, 58, 3) << 1 | bits(block, 52, 1),
+        bits(block, 51, 1) << 3 | bits(block, 49, 3),
+    ];
+    let c1 = [bits(block, 46, 4), bits(block, 42, 4), bits(block, 38, 4)];
+
+    let order = ((c0[0] << 8 | c0[1] << 4 | c0[2]) >= (c1[0] << 8 | c1[1] << 4 | c1[2])) as i32;
+    let d = DISTANCES[(bits(block, 34, 1) << 2 | bits(block, 32, 1) << 1 | order) as usize];
+
+    let c0 = c0.map(|c| extend(c, 4));
+    let c1 = c1.map(|c| extend(c, 4));
+    let palette = [
+        clamp_rgb([c0[0] + d, c0[1] + d, c0[2] + d]),
+        clamp_rgb([c0[0] - d, c0[1] - d, c0[2] - d]),
+        clamp_rgb([c1[0] + d, c1[1] + d, c1[2] + d]),
+        clamp_rgb([c1[0] - d, c1[1] - d, c1[2] - d]),
+    ];
+
+    decode_paint(block, palette, opaque)
+}
+
+fn decode_paint(block: u64, palette: [[u8; 4]; 4], opaque: bool) -> [[u8; 4]; 16] {
+    std::array::from_fn(|i| match texel_index(block, i) {
+        2 if !opaque => [0, 0, 0, 0],
+        index => palette[index],
+    })
+}
+
+fn decode_planar(block: u64) -> [[u8; 4]; 16] {
+    let o = [
+        extend(bits(block, 62, 6), 6),
+        extend(bits(block, 56, 1) << 6 | bits(block, 54, 6), 7),
+        extend(bits(block, 48, 1) << 5 | bits(block, 44, 2) << 3 | bits(block, 41, 3), 6),
+    ];
+    let h = [
+        extend(bits(block, 38, 5) << 1 | bits(block, 32, 1), 6),
+        extend(bits(block, 31, 7), 7),
+        extend(bits(block, 24, 6), 6),
+    ];
+    let v = [extend(bits(block, 18, 6), 6), extend(bits(block, 12, 7), 7), extend(bits(block, 5, 6), 6)];
+
+    std::array::from_fn(|i| {
+        let (x, y) = ((i / 4) as i32, (i % 4) as i32);
+        let channel = |c: usize| (x * (h[c] - o[c]) + y * (v[c] - o[c]) + 4 * o[c] + 2) >> 2;
+        clamp_rgb([channel(0), channel(1), channel(2)])
+    })
+}
+
+/// Decodes an EAC block to 11-bit values in row-major order.
+/// The 8-bit alpha block of `Etc2Rgba8`, `base + modifier * multiplier`. Unlike the 11-bit
+/// channels a multiplier of 0 isn't special, it gives every texel the base value.
+fn decode_eac_alpha(block: u64) -> [u8; 16] {
+    let base = bits(block, 63, 8);
+    let multiplier = bits(block, 55, 4);
+    let modifiers = EAC_MODIFIERS[bits(block, 51, 4) as usize];
+
+    std::array::from_fn(|i| {
+        let texel = (i % 4 * 4 + i / 4) as u32;
+        let modifier = modifiers[bits(block, 47 - 3 * texel, 3) as usize];
+        (base + modifier * multiplier).clamp(0, 255) as u8
+    })
+}
+
+fn decode_eac(block: u64) -> [i32; 16] {
+    let base = bits(block, 63, 8);
+    let multiplier = bits(block, 55, 4);
+    let modifiers = EAC_MODIFIERS[bits(block, 51, 4) as usize];
+
+    std::array::from_fn(|i| {
+        let texel = (i % 4 * 4 + i / 4) as u32;
+        let modifier = modifiers[bits(block, 47 - 3 * texel, 3) as usize];
+        let value = if multiplier == 0 {
+            base * 8 + 4 + modifier
+        } else {
+            base * 8 + 4 + modifier * multiplier * 8
+        };
+        value.clamp(0, 2047)
+    })
+}
+
+/// Same as `decode_eac` for signed blocks, whose base is an `i8` and values range from -1023 to 1023.
+fn decode_eac_snorm(block: u64) -> [i32; 16] {
+    let base = ((bits(block, 63, 8) << 24) >> 24).max(-127);
+    let multiplier = bits(block, 55, 4);
+    let modifiers = EAC_MODIFIERS[bits(block, 51, 4) as usize];
+
+    std::array::from_fn(|i| {
+        let texel = (i % 4 * 4 + i / 4) as u32;
+        let modifier = modifiers[bits(block, 47 - 3 * texel, 3) as usize];
+        let value = if multiplier == 0 {
+            base * 8 + modifier
+        } else {
+            base * 8 + modifier * multiplier * 8
+        };
+        value.clamp(-1023, 1023)
+    })
+}
+
+fn transpose(texels: [[u8; 4]; 16]) -> [[u8; 4]; 16] {
+    std::array::from_fn(|i| texels[(i % 4) * 4 + i / 4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_individual_mode() {
+        // Both subblocks at 0x88 with the first modifier table, and column-major texel 1, the
+        // first texel of the second row, using the last index.
+        let block = [0x88, 0x88, 0x88, 0x00, 0x00, 0x02, 0x00, 0x02];
+        let texels = decode_rgb8(&block);
+
+        assert_eq!(texels[4], [128, 128, 128, 255]);
+        for (i, texel) in texels.iter().enumerate().filter(|&(i, _)| i != 4) {
+            assert_eq!(*texel, [138, 138, 138, 255], "texel {}", i);
+        }
+    }
+
+    #[test]
+    fn decodes_differential_mode() {
+        let block = [0x80, 0x80, 0x80, 0x02, 0, 0, 0, 0];
+
+        assert_eq!(decode_rgb8(&block), [[134, 134, 134, 255]; 16]);
+    }
+
+    #[test]
+    fn decodes_punchthrough_alpha() {
+        // Not opaque, so index 0 has no modifier and index 2, used by the first texel, is transparent.
+        let block = [0x80, 0x80, 0x80, 0x00, 0x00, 0x01, 0x00, 0x00];
+        let texels = decode_rgb8a1(&block);
+
+        assert_eq!(texels[0], [0, 0, 0, 0]);
+        assert!(texels[1..].iter().all(|&texel| texel == [132, 132, 132, 255]));
+    }
+
+    #[test]
+    fn decodes_eac_channels() {
+        let full = [255, 0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let empty = [0, 0xf0, 0x6d, 0xb6, 0xdb, 0x6d, 0xb6, 0xdb];
+
+        assert_eq!(decode_r11(&full), [[255, 0, 0, 255]; 16]);
+        assert_eq!(decode_rg11(&[full, empty].concat()), [[255, 0, 0, 255]; 16]);
+
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&full);
+        block[8..].copy_from_slice(&[0x80, 0x80, 0x80, 0x02, 0, 0, 0, 0]);
+        assert_eq!(decode_rgba8(&block), [[134, 134, 134, 255]; 16]);
+    }
+
+    #[test]
+    fn decodes_eac_alpha_with_zero_multiplier() {
+        // Base 100 with the largest modifier of the last table, ignored at multiplier 0.
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&[100, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        block[8..].copy_from_slice(&[0x80, 0x80, 0x80, 0x02, 0, 0, 0, 0]);
+        assert_eq!(decode_rgba8(&block), [[134, 134, 134, 100]; 16]);
+
+        // Multiplier 3 with the first modifier of the first table.
+        block[..8].copy_from_slice(&[100, 0x30, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(decode_rgba8(&block), [[134, 134, 134, 91]; 16]);
+    }
+
+    #[test]
+    fn decodes_signed_eac_channels() {
+        // -128 decodes the same as -127.
+        let min = [0x80, 0x00, 0, 0, 0, 0, 0, 0];
+        let max = [0x7f, 0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+
+        assert_eq!(decode_r11_snorm(&min), [[0x81, 0, 0, 127]; 16]);
+        assert_eq!(decode_rg11_snorm(&[max, min].concat()), [[0x7f, 0x81, 0, 127]; 16]);
+    }
+}
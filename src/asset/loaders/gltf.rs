@@ -0,0 +1,282 @@
+use std::{collections::{HashMap, HashSet}, sync::Arc};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use bevy_ecs::{world::World, entity::Entity};
+
+use crate::{asset::{asset_manager::AssetManager, handle::Handle, mesh::{MeshData, MeshSettings}, mesh_processing, sources::AssetSources, texture::{ColorSpace, FilterMode, TextureSettings, WrapMode}, Mesh, Texture}, component, engine::context::Context};
+
+/// Imports a `.gltf`/`.glb` file through the `AssetManager`'s sources, adds its meshes and
+/// textures to it and spawns one entity per node of the default scene.
+///
+/// Meshes and textures are added under labeled paths, `scene.gltf#mesh0/primitive1` and
+/// `scene.gltf#texture2`, so their handles stay valid across runs and `Mesh::load` and
+/// `Texture::load` can read them back out of the file.
+///
+/// A node whose mesh has several primitives gets the first one, the rest are spawned as
+/// extra entities sharing the node's transform. Every mesh is processed with `settings`.
+pub async fn spawn_scene(context: &Context, asset_manager: &mut AssetManager, world: &mut World, file_path: &str, settings: &MeshSettings) -> Result<Vec<Entity>> {
+    let (document, buffers, images) = import(&asset_manager.sources(), file_path).await?;
+
+    let scene = document.default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| anyhow!("{}: no scenes found", file_path))?;
+
+    let mut importer = Importer {
+        context,
+        asset_manager,
+        file_path,
+        settings,
+        buffers: &buffers,
+        images: &images,
+        color_textures: color_textures(&document),
+        textures: HashMap::new(),
+        meshes: HashMap::new(),
+    };
+
+    let mut entities = Vec::new();
+    for node in scene.nodes() {
+        importer.spawn_node(world, &node, glam::Mat4::IDENTITY, &mut entities)?;
+    }
+
+    Ok(entities)
+}
+
+struct Importer<'a> {
+    context: &'a Context,
+    asset_manager: &'a mut AssetManager,
+    file_path: &'a str,
+    settings: &'a MeshSettings,
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [::gltf::image::Data],
+    /// Textures loaded as `ColorSpace::Srgb`, see `color_textures`.
+    color_textures: HashSet<usize>,
+
+    textures: HashMap<usize, Handle<Texture>>,
+    meshes: HashMap<(usize, usize), Handle<Mesh>>,
+}
+
+impl<'a> Importer<'a> {
+    fn spawn_node(&mut self, world: &mut World, node: &::gltf::Node, parent: glam::Mat4, entities: &mut Vec<Entity>) -> Result<()> {
+        let matrix = parent * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        let mut primitives = Vec::new();
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+                    log::warn!("{}: skipping non-triangle primitive in mesh {}", self.file_path, mesh.index());
+                    continue;
+                }
+
+                let mesh_handle = self.get_mesh(&mesh, &primitive)?;
+                let texture_handle = match primitive.material().pbr_metallic_roughness().base_color_texture() {
+                    Some(info) => Some(self.get_texture(&info.texture())?),
+                    None => None,
+                };
+                primitives.push((mesh_handle, texture_handle));
+            }
+        }
+
+        let mut primitives = primitives.into_iter();
+        let mut entity = world.spawn(component::Transform::from_matrix(matrix));
+        if let Some((mesh, texture)) = primitives.next() {
+            entity.insert(component::Mesh::new(mesh));
+            if let Some(texture) = texture {
+                entity.insert(component::Texture::new(texture));
+            }
+        }
+        entities.push(entity.id());
+
+        for (mesh, texture) in primitives {
+            let mut entity = world.spawn((component::Transform::from_matrix(matrix), component::Mesh::new(mesh)));
+            if let Some(texture) = texture {
+                entity.insert(component::Texture::new(texture));
+            }
+            entities.push(entity.id());
+        }
+
+        for child in node.children() {
+            self.spawn_node(world, &child, matrix, entities)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_mesh(&mut self, mesh: &::gltf::Mesh, primitive: &::gltf::Primitive) -> Result<Handle<Mesh>> {
+        let key = (mesh.index(), primitive.index());
+        if let Some(handle) = self.meshes.get(&key) {
+            return Ok(handle.clone());
+        }
+
+        let mesh_asset = read_mesh(self.context, self.file_path, self.buffers, mesh, primitive, self.settings)?;
+        let file_path = format!("{}#{}", self.file_path, mesh_label(mesh.index(), primitive.index()));
+        let handle = self.asset_manager.add_labeled(&file_path, Arc::new(mesh_asset), self.settings.clone());
+        self.meshes.insert(key, handle.clone());
+
+        Ok(handle)
+    }
+
+    fn get_texture(&mut self, texture: &::gltf::Texture) -> Result<Handle<Texture>> {
+        if let Some(handle) = self.textures.get(&texture.index()) {
+            return Ok(handle.clone());
+        }
+
+        let settings = texture_settings(texture, self.color_textures.contains(&texture.index()));
+        let texture_asset = read_image(self.context, self.file_path, self.images, texture.source().index(), &settings)?;
+        let file_path = format!("{}#{}", self.file_path, texture_label(texture.index()));
+        let handle = self.asset_manager.add_labeled(&file_path, Arc::new(texture_asset), settings);
+        self.textures.insert(texture.index(), handle.clone());
+
+        Ok(handle)
+    }
+}
+
+fn mesh_label(mesh: usize, primitive: usize) -> String {
+    format!("mesh{}/primitive{}", mesh, primitive)
+}
+
+fn texture_label(texture: usize) -> String {
+    format!("texture{}", texture)
+}
+
+/// Textures some material samples as color. Every other texture holds data such as normals or
+/// metallic-roughness and is loaded as `ColorSpace::Linear`.
+fn color_textures(document: &::gltf::Document) -> HashSet<usize> {
+    document.materials()
+        .flat_map(|material| [
+            material.pbr_metallic_roughness().base_color_texture().map(|info| info.texture().index()),
+            material.emissive_texture().map(|info| info.texture().index()),
+        ])
+        .flatten()
+        .collect()
+}
+
+/// Maps the texture's sampler onto `TextureSettings`. Min filters without a mipmap mode sample
+/// the base level only, so they turn mipmaps off.
+fn texture_settings(texture: &::gltf::Texture, color: bool) -> TextureSettings {
+    use ::gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let wrap = |mode| match mode {
+        WrappingMode::ClampToEdge => WrapMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => WrapMode::MirrorRepeat,
+        WrappingMode::Repeat => WrapMode::Repeat,
+    };
+
+    let sampler = texture.sampler();
+    let (wrap_s, wrap_t) = (wrap(sampler.wrap_s()), wrap(sampler.wrap_t()));
+    let mut settings = TextureSettings {
+        color_space: if color { ColorSpace::Srgb } else { ColorSpace::Linear },
+        wrap: wrap_s,
+        wrap_v: (wrap_t != wrap_s).then_some(wrap_t),
+        ..Default::default()
+    };
+
+    if let Some(mag_filter) = sampler.mag_filter() {
+        settings.mag_filter = match mag_filter {
+            MagFilter::Nearest => FilterMode::Nearest,
+            MagFilter::Linear => FilterMode::Linear,
+        };
+    }
+
+    if let Some(min_filter) = sampler.min_filter() {
+        let (min_filter, mipmap_filter) = match min_filter {
+            MinFilter::Nearest => (FilterMode::Nearest, None),
+            MinFilter::Linear => (FilterMode::Linear, None),
+            MinFilter::NearestMipmapNearest => (FilterMode::Nearest, Some(FilterMode::Nearest)),
+            MinFilter::LinearMipmapNearest => (FilterMode::Linear, Some(FilterMode::Nearest)),
+            MinFilter::NearestMipmapLinear => (FilterMode::Nearest, Some(FilterMode::Linear)),
+            MinFilter::LinearMipmapLinear => (FilterMode::Linear, Some(FilterMode::Linear)),
+        };
+        settings.min_filter = min_filter;
+        match mipmap_filter {
+            Some(mipmap_filter) => settings.mipmap_filter = mipmap_filter,
+            None => settings.mipmaps = false,
+        }
+    }
+
+    settings
+}
+
+/// Loads a single primitive of a glTF file, labeled `mesh{index}/primitive{index}`.
+pub async fn load_mesh(context: &Context, sources: &AssetSources, file_path: &str, label: &str, settings: &MeshSettings) -> Result<Mesh> {
+    let (mesh_index, primitive_index) = label.strip_prefix("mesh")
+        .and_then(|label| label.split_once("/primitive"))
+        .and_then(|(mesh, primitive)| Some((mesh.parse::<usize>().ok()?, primitive.parse::<usize>().ok()?)))
+        .ok_or_else(|| anyhow!("{}: invalid mesh label '{}'", file_path, label))?;
+
+    let (document, buffers, _) = import(sources, file_path).await?;
+    let mesh = document.meshes().nth(mesh_index)
+        .ok_or_else(|| anyhow!("{}: mesh {} not found", file_path, mesh_index))?;
+    let primitive = mesh.primitives().nth(primitive_index)
+        .ok_or_else(|| anyhow!("{}: mesh {} has no primitive {}", file_path, mesh_index, primitive_index))?;
+
+    read_mesh(context, file_path, &buffers, &mesh, &primitive, settings)
+}
+
+/// Loads a single texture of a glTF file, labeled `texture{index}`.
+pub async fn load_texture(context: &Context, sources: &AssetSources, file_path: &str, label: &str, settings: &TextureSettings) -> Result<Texture> {
+    let texture_index = label.strip_prefix("texture")
+        .and_then(|index| index.parse::<usize>().ok())
+        .ok_or_else(|| anyhow!("{}: invalid texture label '{}'", file_path, label))?;
+
+    let (document, _, images) = import(sources, file_path).await?;
+    let texture = document.textures().nth(texture_index)
+        .ok_or_else(|| anyhow!("{}: texture {} not found", file_path, texture_index))?;
+
+    read_image(context, file_path, &images, texture.source().index(), settings)
+}
+
+/// Files on disk are imported from their path so external buffers and images resolve.
+async fn import(sources: &AssetSources, file_path: &str) -> Result<(::gltf::Document, Vec<::gltf::buffer::Data>, Vec<::gltf::image::Data>)> {
+    match sources.local_path(file_path) {
+        Some(local_path) => ::gltf::import(local_path),
+        None => ::gltf::import_slice(sources.read(file_path).await?),
+    }.map_err(|e| anyhow!("{}: {}", file_path, e))
+}
+
+fn read_mesh(context: &Context, file_path: &str, buffers: &[::gltf::buffer::Data], mesh: &::gltf::Mesh, primitive: &::gltf::Primitive, settings: &MeshSettings) -> Result<Mesh> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let positions: Vec<[f32; 3]> = reader.read_positions()
+        .ok_or_else(|| anyhow!("{}: mesh {} has a primitive without positions", file_path, mesh.index()))?
+        .collect();
+    let vertex_count = positions.len();
+
+    let mut data = MeshData {
+        tex_coords: reader.read_tex_coords(0)
+            .map_or_else(|| vec![[0.0, 0.0]; vertex_count], |tex_coords| tex_coords.into_f32().collect()),
+        normals: reader.read_normals()
+            .map_or_else(|| vec![[0.0, 0.0, 0.0]; vertex_count], Iterator::collect),
+        tangents: reader.read_tangents().map(Iterator::collect),
+        colors: reader.read_colors(0).map(|colors| colors.into_rgba_f32().collect()),
+        tex_coords1: reader.read_tex_coords(1).map(|tex_coords| tex_coords.into_f32().collect()),
+        joints: reader.read_joints(0).map(|joints| joints.into_u16().collect()),
+        weights: reader.read_weights(0).map(|weights| weights.into_f32().collect()),
+        indices: match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertex_count as u32).collect(),
+        },
+        positions,
+    };
+
+    mesh_processing::process(&mut data, settings)
+        .with_context(|| format!("{}: unable to process mesh {}", file_path, mesh.index()))?;
+
+    Mesh::with_lods(context, &mesh_processing::generate_lods(data, settings))
+        .with_context(|| format!("{}: invalid mesh {}", file_path, mesh.index()))
+}
+
+fn read_image(context: &Context, file_path: &str, images: &[::gltf::image::Data], image_index: usize, settings: &TextureSettings) -> Result<Texture> {
+    let data = &images[image_index];
+    let (width, height, pixels) = (data.width, data.height, data.pixels.clone());
+
+    use ::gltf::image::Format;
+    let img = match data.format {
+        Format::R8 => image::GrayImage::from_raw(width, height, pixels).map(image::DynamicImage::ImageLuma8),
+        Format::R8G8 => image::GrayAlphaImage::from_raw(width, height, pixels).map(image::DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => image::RgbImage::from_raw(width, height, pixels).map(image::DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => image::RgbaImage::from_raw(width, height, pixels).map(image::DynamicImage::ImageRgba8),
+        format => bail!("{}: image {} has unsupported format {:?}", file_path, image_index, format),
+    }.ok_or_else(|| anyhow!("{}: image {} has invalid dimensions", file_path, image_index))?;
+
+    Texture::from_image(context, &img, settings)
+}
\ No newline at end of file
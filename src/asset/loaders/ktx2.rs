@@ -0,0 +1,108 @@
+use anyhow::{anyhow, bail, Result};
+use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+use super::compressed::CompressedImage;
+
+/// Reads a 2D `.ktx2` texture and all of its mip levels.
+pub fn parse(bytes: &[u8], file_path: &str) -> Result<CompressedImage> {
+    let reader = ::ktx2::Reader::new(bytes)
+        .map_err(|e| anyhow!("{}: {}", file_path, e))?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        bail!("{}: supercompression {:?} is not supported", file_path, scheme);
+    }
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count != 1 {
+        bail!("{}: only single 2D textures are supported", file_path);
+    }
+
+    let format = header.format
+        .ok_or_else(|| anyhow!("{}: textures without a format are not supported", file_path))?;
+    let format = texture_format(format)
+        .ok_or_else(|| anyhow!("{}: unsupported format {:?}", file_path, format))?;
+
+    let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+    let mut levels = Vec::with_capacity(reader.levels().len());
+    for (level, data) in reader.levels().enumerate() {
+        if data.len() != CompressedImage::level_size(format, width, height, level as u32) {
+            bail!("{}: mip level {} has the wrong size", file_path, level);
+        }
+        levels.push(data.to_vec());
+    }
+
+    Ok(CompressedImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+fn texture_format(format: ::ktx2::Format) -> Option<TextureFormat> {
+    use ::ktx2::Format as K;
+
+    let astc = |block, srgb| TextureFormat::Astc {
+        block,
+        channel: if srgb { AstcChannel::UnormSrgb } else { AstcChannel::Unorm },
+    };
+
+    Some(match format {
+        K::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        K::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        K::R16G16B16A16_SFLOAT => TextureFormat::Rgba16Float,
+        K::R32G32B32A32_SFLOAT => TextureFormat::Rgba32Float,
+        K::BC1_RGB_UNORM_BLOCK | K::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        K::BC1_RGB_SRGB_BLOCK | K::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        K::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        K::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        K::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        K::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        K::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        K::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        K::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        K::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        K::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        K::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbFloat,
+        K::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        K::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        K::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        K::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        K::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        K::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        K::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        K::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        K::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        K::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        K::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        K::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+        K::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, false),
+        K::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, true),
+        K::ASTC_5x4_UNORM_BLOCK => astc(AstcBlock::B5x4, false),
+        K::ASTC_5x4_SRGB_BLOCK => astc(AstcBlock::B5x4, true),
+        K::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, false),
+        K::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, true),
+        K::ASTC_6x5_UNORM_BLOCK => astc(AstcBlock::B6x5, false),
+        K::ASTC_6x5_SRGB_BLOCK => astc(AstcBlock::B6x5, true),
+        K::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, false),
+        K::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, true),
+        K::ASTC_8x5_UNORM_BLOCK => astc(AstcBlock::B8x5, false),
+        K::ASTC_8x5_SRGB_BLOCK => astc(AstcBlock::B8x5, true),
+        K::ASTC_8x6_UNORM_BLOCK => astc(AstcBlock::B8x6, false),
+        K::ASTC_8x6_SRGB_BLOCK => astc(AstcBlock::B8x6, true),
+        K::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, false),
+        K::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, true),
+        K::ASTC_10x5_UNORM_BLOCK => astc(AstcBlock::B10x5, false),
+        K::ASTC_10x5_SRGB_BLOCK => astc(AstcBlock::B10x5, true),
+        K::ASTC_10x6_UNORM_BLOCK => astc(AstcBlock::B10x6, false),
+        K::ASTC_10x6_SRGB_BLOCK => astc(AstcBlock::B10x6, true),
+        K::ASTC_10x8_UNORM_BLOCK => astc(AstcBlock::B10x8, false),
+        K::ASTC_10x8_SRGB_BLOCK => astc(AstcBlock::B10x8, true),
+        K::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, false),
+        K::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, true),
+        K::ASTC_12x10_UNORM_BLOCK => astc(AstcBlock::B12x10, false),
+        K::ASTC_12x10_SRGB_BLOCK => astc(AstcBlock::B12x10, true),
+        K::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, false),
+        K::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, true),
+        _ => return None,
+    })
+}
@@ -0,0 +1,8 @@
+pub mod obj;
+pub mod gltf;
+pub mod compressed;
+pub mod ktx2;
+pub mod dds;
+mod bcn;
+mod bptc;
+mod etc2;
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::engine::vertex::Vertex;
+
+/// Every object and group of an OBJ file merged into one indexed mesh.
+pub struct ObjData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+/// Position, texcoord and normal indices of a single face corner.
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+struct Parser<'a> {
+    file_path: &'a str,
+    line: usize,
+
+    positions: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    lookup: HashMap<VertexKey, u32>,
+}
+
+pub fn parse(source: &str, file_path: &str) -> Result<ObjData> {
+    let mut parser = Parser {
+        file_path,
+        line: 0,
+
+        positions: Vec::new(),
+        tex_coords: Vec::new(),
+        normals: Vec::new(),
+
+        vertices: Vec::new(),
+        indices: Vec::new(),
+        lookup: HashMap::new(),
+    };
+
+    for (i, line) in source.lines().enumerate() {
+        parser.line = i + 1;
+        parser.parse_line(line)?;
+    }
+
+    if parser.indices.is_empty() {
+        bail!("{}: no faces found", file_path);
+    }
+
+    Ok(ObjData {
+        vertices: parser.vertices,
+        indices: parser.indices,
+    })
+}
+
+impl<'a> Parser<'a> {
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let line = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = self.floats::<3>(&args)?;
+                self.positions.push([x, y, z]);
+            },
+            "vt" => {
+                // `v` is optional for 1D textures.
+                let [u] = self.floats::<1>(&args)?;
+                let [v] = self.floats::<1>(&args[1..]).unwrap_or([0.0]);
+                self.tex_coords.push([u, 1.0 - v]);
+            },
+            "vn" => {
+                let [x, y, z] = self.floats::<3>(&args)?;
+                self.normals.push([x, y, z]);
+            },
+            "f" => self.parse_face(&args)?,
+            // Objects and groups are merged into one mesh, and materials, smoothing groups,
+            // lines and points don't affect it.
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    fn parse_face(&mut self, args: &[&str]) -> Result<()> {
+        if args.len() < 3 {
+            return Err(self.error(format!("face needs at least 3 vertices, found {}", args.len())));
+        }
+
+        let corners = args.iter()
+            .map(|corner| self.parse_corner(corner))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Triangulate n-gons as a fan around the first corner.
+        for i in 1..corners.len() - 1 {
+            for key in [corners[0], corners[i], corners[i + 1]] {
+                let index = self.get_vertex(key);
+                self.indices.push(index);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_corner(&self, corner: &str) -> Result<VertexKey> {
+        let mut parts = corner.split('/');
+
+        let position = match parts.next() {
+            Some(index) if !index.is_empty() => self.resolve(index, self.positions.len(), "position")?,
+            _ => return Err(self.error(format!("invalid face vertex '{}'", corner))),
+        };
+        let tex_coord = match parts.next() {
+            Some(index) if !index.is_empty() => Some(self.resolve(index, self.tex_coords.len(), "texcoord")?),
+            _ => None,
+        };
+        let normal = match parts.next() {
+            Some(index) if !index.is_empty() => Some(self.resolve(index, self.normals.len(), "normal")?),
+            _ => None,
+        };
+
+        Ok((position, tex_coord, normal))
+    }
+
+    /// Converts a 1-based (or negative, relative) OBJ index into a 0-based one.
+    fn resolve(&self, index: &str, len: usize, kind: &str) -> Result<usize> {
+        let index: i64 = index.parse()
+            .map_err(|_| self.error(format!("invalid {} index '{}'", kind, index)))?;
+
+        let resolved = match index {
+            i if i > 0 => i - 1,
+            i if i < 0 => len as i64 + i,
+            _ => -1,
+        };
+
+        if resolved < 0 || resolved >= len as i64 {
+            return Err(self.error(format!("{} index {} out of range", kind, index)));
+        }
+
+        Ok(resolved as usize)
+    }
+
+    fn get_vertex(&mut self, key: VertexKey) -> u32 {
+        if let Some(&index) = self.lookup.get(&key) {
+            return index;
+        }
+
+        let (position, tex_coord, normal) = key;
+        let index = self.vertices.len() as u32;
+
+        self.vertices.push(Vertex {
+            position: self.positions[position],
+            tex_coords: tex_coord.map_or([0.0, 0.0], |i| self.tex_coords[i]),
+            normal: normal.map_or([0.0, 0.0, 0.0], |i| self.normals[i]),
+        });
+        self.lookup.insert(key, index);
+
+        index
+    }
+
+    fn floats<const N: usize>(&self, args: &[&str]) -> Result<[f32; N]> {
+        if args.len() < N {
+            return Err(self.error(format!("expected {} values, found {}", N, args.len())));
+        }
+
+        let mut values = [0.0; N];
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg.parse()
+                .map_err(|_| self.error(format!("invalid number '{}'", arg)))?;
+        }
+
+        Ok(values)
+    }
+
+    fn error(&self, message: String) -> anyhow::Error {
+        anyhow!("{}:{}: {}", self.file_path, self.line, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangulates_quads_as_a_fan() {
+        let obj = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n", "quad.obj").unwrap();
+
+        assert_eq!(obj.vertices.len(), 4);
+        assert_eq!(obj.indices, [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn deduplicates_shared_corners() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/1/1 3/1/1
+f 1/1/1 3/1/1 2/2/1
+";
+        let obj = parse(source, "shared.obj").unwrap();
+
+        // `2/2/1` differs from `2/1/1` by its texcoord, so it's the only new vertex.
+        assert_eq!(obj.vertices.len(), 4);
+        assert_eq!(obj.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(obj.vertices[3].tex_coords, [1.0, 0.0]);
+        assert_eq!(obj.vertices[3].normal, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn resolves_relative_indices_and_missing_attributes() {
+        let obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//-1 -2//-1 -1//-1\n", "relative.obj").unwrap();
+
+        assert_eq!(obj.indices, [0, 1, 2]);
+        assert_eq!(obj.vertices[2].position, [0.0, 1.0, 0.0]);
+        assert_eq!(obj.vertices[2].tex_coords, [0.0, 0.0]);
+        assert_eq!(obj.vertices[2].normal, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn merges_objects_and_ignores_comments() {
+        let source = "\
+# two triangles in separate objects
+mtllib crate.mtl
+o first
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3 # trailing comment
+g second
+usemtl wood
+s 1
+f 3 2 1
+";
+        let obj = parse(source, "objects.obj").unwrap();
+
+        assert_eq!(obj.vertices.len(), 3);
+        assert_eq!(obj.indices, [0, 1, 2, 2, 1, 0]);
+    }
+
+    #[test]
+    fn errors_name_the_file_and_line() {
+        let error = parse("v 0 0 0\nv 1 0 0\nf 1 2 3\n", "broken.obj").err().unwrap();
+        assert_eq!(error.to_string(), "broken.obj:3: position index 3 out of range");
+
+        let error = parse("v 0 zero 0\n", "broken.obj").err().unwrap();
+        assert_eq!(error.to_string(), "broken.obj:1: invalid number 'zero'");
+
+        let error = parse("v 0 0 0\nf 1 1\n", "broken.obj").err().unwrap();
+        assert_eq!(error.to_string(), "broken.obj:2: face needs at least 3 vertices, found 2");
+    }
+
+    #[test]
+    fn rejects_files_without_faces() {
+        assert!(parse("v 0 0 0\n", "empty.obj").is_err());
+    }
+}
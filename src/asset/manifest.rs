@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+
+/// Named assets declared in `assets.yaml`:
+///
+/// ```yaml
+/// stone_bricks:
+///   type: texture
+///   path: res/textures/stone_bricks.jpg
+///   options:
+///     wrap: repeat
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(transparent)]
+pub struct Manifest {
+    pub assets: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct ManifestEntry {
+    /// `Asset::TYPE_NAME` of a registered asset type.
+    #[serde(rename = "type")]
+    pub asset_type: String,
+    pub path: String,
+    /// Deserialized into the asset type's `Asset::Settings`.
+    #[serde(default)]
+    pub options: serde_yaml::Value,
+}
+
+impl Manifest {
+    pub fn from_file(file_path: &str) -> Result<Self> {
+        let source = std::fs::read_to_string(file_path)
+            .with_context(|| format!("Unable to read file: {}", file_path))?;
+
+        Self::parse(&source)
+            .with_context(|| format!("Invalid asset manifest: {}", file_path))
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        // An empty document deserializes as null rather than an empty map.
+        if source.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_yaml::from_str(source)?)
+    }
+}
\ No newline at end of file
@@ -1,56 +1,516 @@
-use std::sync::Arc;
+use std::{io::Read, sync::Arc, path::Path};
 
+use anyhow::{bail, Context as _, Result};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use wgpu::util::DeviceExt;
 
-use crate::{engine::{vertex::Vertex, context::Context}, util::cast_slice};
+use crate::{engine::{bounds::{Aabb, BoundingSphere}, vertex::{Vertex, VertexAttribute, VertexLayout}, context::Context}, util::cast_slice};
 
-use super::{Asset, primitives::quad};
+use super::{Asset, loaders::{gltf, obj}, mesh_processing, primitives::PrimitiveData, sources::{AssetSources, archive::read_u32}};
+
+const MAGIC: &[u8; 4] = b"RMSH";
+const VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalMode {
+    /// Keeps the file's normals, or generates smooth ones if it has none.
+    #[default]
+    Import,
+    Smooth,
+    Flat,
+}
+
+/// Processing applied to imported meshes before upload, see `mesh_processing`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeshSettings {
+    pub normals: NormalMode,
+    /// Generates MikkTSpace tangents for normal mapping.
+    pub tangents: bool,
+    /// Merges duplicate vertices.
+    pub weld: bool,
+    /// Reorders triangles and vertices for the vertex cache and less overdraw.
+    pub optimize: bool,
+    /// Levels of detail to generate, from most to least detailed.
+    pub lods: Vec<LodSettings>,
+}
+
+/// A generated level of detail:
+///
+/// ```yaml
+/// lods:
+///   - { ratio: 0.5, screen_size: 0.3 }
+///   - { ratio: 0.1, screen_size: 0.1, max_error: 0.05 }
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodSettings {
+    /// Fraction of the triangles to keep.
+    pub ratio: f32,
+    /// Projected height, as a fraction of the screen, below which this level replaces the previous one.
+    pub screen_size: f32,
+    /// Simplification stops early rather than deviate further than this, relative to the size of the mesh.
+    pub max_error: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            ratio: 0.5,
+            screen_size: 0.25,
+            max_error: 0.02,
+        }
+    }
+}
+
+/// Margin around `MeshLod::screen_size` that the projected size has to cross before switching
+/// levels, so meshes hovering around a threshold don't keep popping between two.
+pub const LOD_HYSTERESIS: f32 = 0.1;
+
+/// One level of detail of a `Mesh`, authored or from `mesh_processing::generate_lods`.
+pub struct MeshLevel {
+    pub data: MeshData,
+    /// Smallest projected height, as a fraction of the screen, this level is drawn at.
+    /// The last level is drawn at any size.
+    pub screen_size: f32,
+}
+
+/// Where a level of detail lives in the mesh's buffers.
+#[derive(Clone, Copy, Debug)]
+pub struct MeshLod {
+    pub first_index: u32,
+    pub index_count: u32,
+    pub base_vertex: i32,
+    pub screen_size: f32,
+}
+
+/// CPU-side mesh data, one stream per attribute. Position, UV and normal streams are always
+/// present, the optional ones must be either `None` or as long as `positions`.
+#[derive(Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub tex_coords: Vec<[f32; 2]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+    pub tex_coords1: Option<Vec<[f32; 2]>>,
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn from_vertices(vertices: &[Vertex], indices: Vec<u32>) -> Self {
+        Self {
+            positions: vertices.iter().map(|vertex| vertex.position).collect(),
+            tex_coords: vertices.iter().map(|vertex| vertex.tex_coords).collect(),
+            normals: vertices.iter().map(|vertex| vertex.normal).collect(),
+            indices,
+            ..Default::default()
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Extra attributes this mesh has streams for.
+    pub fn attributes(&self) -> Vec<VertexAttribute> {
+        VertexAttribute::ALL.into_iter()
+            .filter(|&attribute| match attribute {
+                VertexAttribute::Tangent => self.tangents.is_some(),
+                VertexAttribute::Color => self.colors.is_some(),
+                VertexAttribute::TexCoord1 => self.tex_coords1.is_some(),
+                VertexAttribute::Joints => self.joints.is_some(),
+                VertexAttribute::Weights => self.weights.is_some(),
+            })
+            .collect()
+    }
+
+    pub fn layout(&self) -> VertexLayout {
+        VertexLayout::new(&self.attributes())
+    }
+
+    /// 16-bit indices when every vertex can be addressed with them, halving the index buffer.
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        if self.vertex_count() <= u16::MAX as usize + 1 {
+            wgpu::IndexFormat::Uint16
+        } else {
+            wgpu::IndexFormat::Uint32
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let count = self.vertex_count();
+        let lengths = [
+            ("tex_coords", Some(self.tex_coords.len())),
+            ("normals", Some(self.normals.len())),
+            ("tangents", self.tangents.as_ref().map(Vec::len)),
+            ("colors", self.colors.as_ref().map(Vec::len)),
+            ("tex_coords1", self.tex_coords1.as_ref().map(Vec::len)),
+            ("joints", self.joints.as_ref().map(Vec::len)),
+            ("weights", self.weights.as_ref().map(Vec::len)),
+        ];
+
+        for (name, len) in lengths {
+            if let Some(len) = len.filter(|&len| len != count) {
+                bail!("{} has {} entries, expected {}", name, len, count);
+            }
+        }
+
+        if let Some(&index) = self.indices.iter().find(|&&index| index as usize >= count) {
+            bail!("index {} out of range for {} vertices", index, count);
+        }
+
+        Ok(())
+    }
+
+    /// Interleaves every stream following `layout()`.
+    pub fn vertex_bytes(&self) -> Vec<u8> {
+        let layout = self.layout();
+        let mut bytes = Vec::with_capacity(layout.array_stride() as usize * self.vertex_count());
+
+        for i in 0..self.vertex_count() {
+            bytes.extend_from_slice(cast_slice(&self.positions[i]));
+            bytes.extend_from_slice(cast_slice(&self.tex_coords[i]));
+            bytes.extend_from_slice(cast_slice(&self.normals[i]));
+
+            // Same order as `VertexAttribute::ALL`.
+            if let Some(tangents) = &self.tangents {
+                bytes.extend_from_slice(cast_slice(&tangents[i]));
+            }
+            if let Some(colors) = &self.colors {
+                bytes.extend_from_slice(cast_slice(&colors[i]));
+            }
+            if let Some(tex_coords1) = &self.tex_coords1 {
+                bytes.extend_from_slice(cast_slice(&tex_coords1[i]));
+            }
+            if let Some(joints) = &self.joints {
+                bytes.extend_from_slice(cast_slice(&joints[i]));
+            }
+            if let Some(weights) = &self.weights {
+                bytes.extend_from_slice(cast_slice(&weights[i]));
+            }
+        }
+
+        bytes
+    }
+}
 
 pub struct Mesh {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
+    /// Index count of the most detailed level.
     pub index_count: u32,
+    pub index_format: wgpu::IndexFormat,
+    /// Levels of detail, most detailed first. Always has at least one.
+    pub lods: Vec<MeshLod>,
+    /// Layout of `vertex_buffer`, used to pick a matching pipeline.
+    pub layout: VertexLayout,
+    /// Bounds in model space, used for culling.
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
 }
 
 impl Mesh {
-    pub fn new(context: &Context, vertices: &[Vertex], indices: &[u16]) -> Self {
+    pub fn new(context: &Context, data: &MeshData) -> Result<Self> {
+        data.validate()?;
+        Self::from_lods(context, std::iter::once((data, 0.0)))
+    }
+
+    /// Uploads every level into shared buffers. Levels must have the same vertex layout, and
+    /// culling uses the bounds of the first.
+    pub fn with_lods(context: &Context, levels: &[MeshLevel]) -> Result<Self> {
+        let Some(first) = levels.first() else { bail!("mesh has no levels") };
+        for (i, level) in levels.iter().enumerate() {
+            level.data.validate()
+                .with_context(|| format!("Invalid level of detail {}", i))?;
+            if level.data.layout() != first.data.layout() {
+                bail!("level of detail {} has different vertex attributes than the first", i);
+            }
+        }
+
+        Self::from_lods(context, levels.iter().map(|level| (&level.data, level.screen_size)))
+    }
+
+    fn from_lods<'a>(context: &Context, levels: impl Iterator<Item = (&'a MeshData, f32)> + Clone) -> Result<Self> {
+        let (first, _) = levels.clone().next().unwrap();
+
+        let index_format = match levels.clone().all(|(data, _)| data.index_format() == wgpu::IndexFormat::Uint16) {
+            true => wgpu::IndexFormat::Uint16,
+            false => wgpu::IndexFormat::Uint32,
+        };
+
+        let mut vertices = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut lods = Vec::new();
+        for (data, screen_size) in levels {
+            lods.push(MeshLod {
+                first_index: indices.len() as u32,
+                index_count: data.indices.len() as u32,
+                base_vertex: (vertices.len() / first.layout().array_stride() as usize) as i32,
+                screen_size,
+            });
+            vertices.extend_from_slice(&data.vertex_bytes());
+            indices.extend_from_slice(&data.indices);
+        }
+
         let vertex_buffer = context.device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
-                contents: cast_slice(vertices),
+                contents: &vertices,
                 usage: wgpu::BufferUsages::VERTEX,
             }
         );
 
+        let short_indices: Vec<u16>;
+        let indices = match index_format {
+            wgpu::IndexFormat::Uint16 => {
+                short_indices = indices.iter().map(|&index| index as u16).collect();
+                cast_slice(&short_indices)
+            },
+            wgpu::IndexFormat::Uint32 => cast_slice(&indices),
+        };
+
         let index_buffer = context.device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: Some("Index Buffer"),
-                contents: cast_slice(indices),
+                contents: indices,
                 usage: wgpu::BufferUsages::INDEX,
             }
         );
 
-        Self {
+        let positions = || first.positions.iter().copied().map(glam::Vec3::from);
+        let aabb = Aabb::from_points(positions());
+        let bounding_sphere = BoundingSphere::from_points(&aabb, positions());
+
+        Ok(Self {
             vertex_buffer,
             index_buffer,
-            index_count: indices.len() as u32,
+            index_count: first.indices.len() as u32,
+            index_format,
+            lods,
+            layout: first.layout(),
+            aabb,
+            bounding_sphere,
+        })
+    }
+
+    /// Picks the level to draw at `screen_size`, the projected height as a fraction of the
+    /// screen, given the level drawn last frame. See `LOD_HYSTERESIS`.
+    pub fn select_lod(&self, screen_size: f32, current: usize) -> usize {
+        select_lod(&self.lods, screen_size, current)
+    }
+
+    pub fn from_primitive(context: &Context, data: &PrimitiveData) -> Result<Self> {
+        Self::new(context, &MeshData::from_vertices(&data.vertices, data.indices.clone()))
+    }
+
+    pub fn from_obj(context: &Context, source: &str, file_path: &str, settings: &MeshSettings) -> Result<Self> {
+        Self::with_lods(context, &Self::obj_levels(source, file_path, settings)?)
+    }
+
+    /// Parses and processes an `.obj` file into its levels of detail.
+    fn obj_levels(source: &str, file_path: &str, settings: &MeshSettings) -> Result<Vec<MeshLevel>> {
+        let obj = obj::parse(source, file_path)?;
+        let mut data = MeshData::from_vertices(&obj.vertices, obj.indices);
+        mesh_processing::process(&mut data, settings)
+            .with_context(|| format!("Unable to process mesh: {}", file_path))?;
+
+        Ok(mesh_processing::generate_lods(data, settings))
+    }
+}
+
+fn select_lod(lods: &[MeshLod], screen_size: f32, current: usize) -> usize {
+    let last = lods.len() - 1;
+    let current = current.min(last);
+    let target = lods.iter()
+        .position(|lod| screen_size >= lod.screen_size)
+        .unwrap_or(last);
+
+    let switch = match target.cmp(&current) {
+        std::cmp::Ordering::Greater => screen_size < lods[current].screen_size * (1.0 - LOD_HYSTERESIS),
+        std::cmp::Ordering::Less => screen_size >= lods[current - 1].screen_size * (1.0 + LOD_HYSTERESIS),
+        std::cmp::Ordering::Equal => false,
+    };
+
+    if switch { target } else { current }
+}
+
+/// Serializes processed levels for the asset cache, each stream stored as-is so loading
+/// them is a straight copy.
+fn levels_to_bytes(levels: &[MeshLevel]) -> Vec<u8> {
+    fn write<const N: usize>(bytes: &mut Vec<u8>, stream: &[[f32; N]]) {
+        for value in stream.iter().flatten() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+
+    for MeshLevel { data, screen_size } in levels {
+        // One bit per optional stream present, streams follow in `VertexAttribute::ALL` order.
+        let mask = data.attributes().into_iter()
+            .fold(0u32, |mask, attribute| mask | 1 << attribute as u32);
+
+        for value in [screen_size.to_bits(), mask, data.vertex_count() as u32, data.indices.len() as u32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        write(&mut bytes, &data.positions);
+        write(&mut bytes, &data.tex_coords);
+        write(&mut bytes, &data.normals);
+        if let Some(tangents) = &data.tangents {
+            write(&mut bytes, tangents);
+        }
+        if let Some(colors) = &data.colors {
+            write(&mut bytes, colors);
+        }
+        if let Some(tex_coords1) = &data.tex_coords1 {
+            write(&mut bytes, tex_coords1);
+        }
+        if let Some(joints) = &data.joints {
+            for joint in joints.iter().flatten() {
+                bytes.extend_from_slice(&joint.to_le_bytes());
+            }
+        }
+        if let Some(weights) = &data.weights {
+            write(&mut bytes, weights);
+        }
+        for index in &data.indices {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    bytes
+}
+
+fn levels_from_bytes(bytes: &[u8]) -> Result<Vec<MeshLevel>> {
+    fn read<const N: usize>(reader: &mut &[u8], count: usize) -> Result<Vec<[f32; N]>> {
+        let mut stream = vec![[0.0; N]; count];
+        for value in stream.iter_mut().flatten() {
+            *value = f32::from_bits(read_u32(reader)?);
         }
+        Ok(stream)
+    }
+
+    let mut reader = bytes;
+
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        bail!("not a cooked reclipse mesh");
+    }
+
+    let version = read_u32(&mut reader)?;
+    if version != VERSION {
+        bail!("unsupported cooked mesh version {}", version);
+    }
+
+    let mut levels = Vec::new();
+    for _ in 0..read_u32(&mut reader)? {
+        let screen_size = f32::from_bits(read_u32(&mut reader)?);
+        let mask = read_u32(&mut reader)?;
+        let count = read_u32(&mut reader)? as usize;
+        let index_count = read_u32(&mut reader)? as usize;
+        let has = |attribute: VertexAttribute| mask & 1 << attribute as u32 != 0;
+
+        let positions = read(&mut reader, count)?;
+        let tex_coords = read(&mut reader, count)?;
+        let normals = read(&mut reader, count)?;
+        let tangents = has(VertexAttribute::Tangent).then(|| read(&mut reader, count)).transpose()?;
+        let colors = has(VertexAttribute::Color).then(|| read(&mut reader, count)).transpose()?;
+        let tex_coords1 = has(VertexAttribute::TexCoord1).then(|| read(&mut reader, count)).transpose()?;
+        let joints = has(VertexAttribute::Joints).then(|| -> Result<_> {
+            let mut joints = vec![[0u16; 4]; count];
+            for joint in joints.iter_mut().flatten() {
+                let mut bytes = [0; 2];
+                reader.read_exact(&mut bytes)?;
+                *joint = u16::from_le_bytes(bytes);
+            }
+            Ok(joints)
+        }).transpose()?;
+        let weights = has(VertexAttribute::Weights).then(|| read(&mut reader, count)).transpose()?;
+
+        let indices = (0..index_count)
+            .map(|_| read_u32(&mut reader))
+            .collect::<Result<_>>()?;
+
+        levels.push(MeshLevel {
+            data: MeshData {
+                positions,
+                tex_coords,
+                normals,
+                tangents,
+                colors,
+                tex_coords1,
+                joints,
+                weights,
+                indices,
+            },
+            screen_size,
+        });
     }
 
-    
+    Ok(levels)
 }
 
 #[async_trait]
 impl Asset for Mesh {
-    //TODO: CREATE LOAD MESH FN ABOVE AND USE IT HERE
-    async fn load(context: &Context, _file_path: &str) -> Arc<Self> {
-        Arc::new(Mesh::new(context, quad::VERTICES, quad::INDICES))
+    const TYPE_NAME: &'static str = "mesh";
+    type Settings = MeshSettings;
+
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, settings: &MeshSettings) -> Result<Arc<Self>> {
+        if let (gltf_path, Some(label)) = super::split_label(file_path) {
+            return Ok(Arc::new(gltf::load_mesh(context, sources, gltf_path, label, settings).await?));
+        }
+
+        let extension = Path::new(file_path).extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("obj") => {
+                let source = String::from_utf8(sources.read(file_path).await?)
+                    .with_context(|| format!("{}: not valid UTF-8", file_path))?;
+                Ok(Arc::new(Self::from_obj(context, &source, file_path, settings)?))
+            },
+            _ => bail!("{}: unsupported mesh format", file_path),
+        }
+    }
+
+    /// Only `.obj` files are cooked. Labeled glTF primitives are processed from source on every
+    /// load: a `.gltf` can keep its buffers in separate files, which the cache key, a hash of
+    /// the file's own bytes, wouldn't notice changing.
+    fn cookable(file_path: &str) -> bool {
+        file_path.to_ascii_lowercase().ends_with(".obj")
+    }
+
+    fn cook(file_path: &str, bytes: &[u8], settings: &MeshSettings) -> Result<Vec<u8>> {
+        let source = std::str::from_utf8(bytes)
+            .with_context(|| format!("{}: not valid UTF-8", file_path))?;
+
+        Ok(levels_to_bytes(&Self::obj_levels(source, file_path, settings)?))
+    }
+
+    fn from_cooked(context: &Context, cooked: &[u8], _settings: &MeshSettings) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self::with_lods(context, &levels_from_bytes(cooked)?)?))
+    }
+
+    fn gpu_size(&self) -> u64 {
+        self.vertex_buffer.size() + self.index_buffer.size()
     }
 }
 
 pub trait DrawMesh<'a> {
     fn draw_mesh(&mut self, mesh: &'a Mesh);
+    /// Draws level of detail `lod`, clamped to the mesh's least detailed level.
+    fn draw_mesh_lod(&mut self, mesh: &'a Mesh, lod: usize);
 }
 
 impl<'a, 'b> DrawMesh<'b> for wgpu::RenderPass<'a>
@@ -58,8 +518,122 @@ where
     'b: 'a,
 {
     fn draw_mesh(&mut self, mesh: &'b Mesh) {
+        self.draw_mesh_lod(mesh, 0);
+    }
+
+    fn draw_mesh_lod(&mut self, mesh: &'b Mesh, lod: usize) {
+        let lod = mesh.lods[lod.min(mesh.lods.len() - 1)];
+
         self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        self.draw_indexed(0..mesh.index_count, 0, 0..1);
+        self.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+        self.draw_indexed(lod.first_index..lod.first_index + lod.index_count, lod.base_vertex, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> MeshData {
+        MeshData {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            tex_coords: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            normals: vec![[0.0, 0.0, 1.0]; 3],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        }
+    }
+
+    fn assert_same(a: &MeshData, b: &MeshData) {
+        assert_eq!(a.positions, b.positions);
+        assert_eq!(a.tex_coords, b.tex_coords);
+        assert_eq!(a.normals, b.normals);
+        assert_eq!(a.tangents, b.tangents);
+        assert_eq!(a.colors, b.colors);
+        assert_eq!(a.tex_coords1, b.tex_coords1);
+        assert_eq!(a.joints, b.joints);
+        assert_eq!(a.weights, b.weights);
+        assert_eq!(a.indices, b.indices);
+    }
+
+    #[test]
+    fn round_trips_levels_through_bytes() {
+        let skinned = MeshData {
+            tangents: Some(vec![[1.0, 0.0, 0.0, 1.0]; 3]),
+            joints: Some(vec![[0, 1, 2, 65535]; 3]),
+            weights: Some(vec![[0.5, 0.25, 0.25, 0.0]; 3]),
+            ..triangle()
+        };
+        let levels = [
+            MeshLevel { data: skinned, screen_size: 0.5 },
+            MeshLevel { data: triangle(), screen_size: 0.0 },
+        ];
+
+        let loaded = levels_from_bytes(&levels_to_bytes(&levels)).unwrap();
+        assert_eq!(loaded.len(), 2);
+        for (loaded, level) in loaded.iter().zip(&levels) {
+            assert_eq!(loaded.screen_size, level.screen_size);
+            assert_same(&loaded.data, &level.data);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cooks_obj_files() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1\n";
+        let cooked = <Mesh as Asset>::cook("triangle.obj", source.as_bytes(), &MeshSettings::default()).unwrap();
+
+        let levels = levels_from_bytes(&cooked).unwrap();
+        assert_eq!(levels.len(), 1);
+        assert_same(&levels[0].data, &MeshData { tex_coords: vec![[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]], ..triangle() });
+    }
+
+    fn lods(screen_sizes: &[f32]) -> Vec<MeshLod> {
+        screen_sizes.iter()
+            .map(|&screen_size| MeshLod { first_index: 0, index_count: 0, base_vertex: 0, screen_size })
+            .collect()
+    }
+
+    #[test]
+    fn selects_the_level_for_the_screen_size() {
+        let lods = lods(&[0.5, 0.2, 0.0]);
+
+        assert_eq!(select_lod(&lods, 0.8, 0), 0);
+        assert_eq!(select_lod(&lods, 0.1, 0), 2);
+        assert_eq!(select_lod(&lods, 0.8, 2), 0);
+        assert_eq!(select_lod(&lods, 0.3, 7), 1);
+    }
+
+    #[test]
+    fn keeps_the_current_level_within_the_hysteresis() {
+        let lods = lods(&[0.5, 0.2, 0.0]);
+
+        // Just under the threshold of level 0 stays on it until 10% below.
+        assert_eq!(select_lod(&lods, 0.48, 0), 0);
+        assert_eq!(select_lod(&lods, 0.44, 0), 1);
+
+        // And coming back up needs to clear it by 10%.
+        assert_eq!(select_lod(&lods, 0.52, 1), 1);
+        assert_eq!(select_lod(&lods, 0.56, 1), 0);
+
+        // Same around the last level's threshold.
+        assert_eq!(select_lod(&lods, 0.19, 1), 1);
+        assert_eq!(select_lod(&lods, 0.21, 2), 2);
+        assert_eq!(select_lod(&lods, 0.23, 2), 1);
+    }
+
+    #[test]
+    fn single_level_meshes_always_use_it() {
+        let lods = lods(&[0.0]);
+
+        assert_eq!(select_lod(&lods, 0.0, 0), 0);
+        assert_eq!(select_lod(&lods, 1.0, 3), 0);
+    }
+
+    #[test]
+    fn rejects_truncated_bytes() {
+        let bytes = levels_to_bytes(&[MeshLevel { data: triangle(), screen_size: 0.0 }]);
+
+        assert!(levels_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(levels_from_bytes(&[b"RTEX", &bytes[4..]].concat()).is_err());
+    }
+}
@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use glam::Vec3;
+
+use crate::engine::bounds::Aabb;
+
+use super::mesh::{MeshData, MeshLevel, MeshSettings, NormalMode};
+
+/// Vertex cache size assumed by `optimize_vertex_cache` and `optimize_overdraw`.
+const CACHE_SIZE: usize = 16;
+
+/// Runs the steps enabled in `settings`, in the order normals, welding, tangents, then index
+/// and vertex reordering.
+pub fn process(data: &mut MeshData, settings: &MeshSettings) -> Result<()> {
+    data.validate()?;
+
+    match settings.normals {
+        NormalMode::Import if data.normals.iter().all(|&normal| normal == [0.0; 3]) => compute_smooth_normals(data),
+        NormalMode::Import => {},
+        NormalMode::Smooth => compute_smooth_normals(data),
+        NormalMode::Flat => compute_flat_normals(data),
+    }
+
+    if settings.weld {
+        weld(data);
+    }
+
+    if settings.tangents {
+        generate_tangents(data)?;
+    }
+
+    if settings.optimize {
+        optimize_vertex_cache(data);
+        optimize_overdraw(data);
+        optimize_vertex_fetch(data);
+    }
+
+    Ok(())
+}
+
+/// Simplifies `data` into each of `settings.lods` in turn, returning it as the first level
+/// followed by the generated ones.
+pub fn generate_lods(data: MeshData, settings: &MeshSettings) -> Vec<MeshLevel> {
+    let mut levels = vec![MeshLevel {
+        screen_size: settings.lods.first().map_or(0.0, |lod| lod.screen_size),
+        data,
+    }];
+
+    for (i, lod) in settings.lods.iter().enumerate() {
+        let source = &levels[0].data;
+        let target_index_count = (source.indices.len() as f32 * lod.ratio) as usize / 3 * 3;
+
+        let mut data = MeshData {
+            indices: simplify(source, target_index_count, lod.max_error),
+            ..source.clone()
+        };
+        if settings.optimize {
+            optimize_vertex_cache(&mut data);
+            optimize_overdraw(&mut data);
+        }
+        optimize_vertex_fetch(&mut data);
+
+        levels.push(MeshLevel {
+            screen_size: settings.lods.get(i + 1).map_or(0.0, |lod| lod.screen_size),
+            data,
+        });
+    }
+
+    levels
+}
+
+/// Area-weighted vertex normals. Vertices sharing a position get the same normal, so UV seams
+/// don't show up as hard edges.
+pub fn compute_smooth_normals(data: &mut MeshData) {
+    let mut groups = HashMap::new();
+    let group: Vec<usize> = data.positions.iter()
+        .map(|position| {
+            let next = groups.len();
+            *groups.entry(position.map(f32::to_bits)).or_insert(next)
+        })
+        .collect();
+
+    let mut normals = vec![Vec3::ZERO; groups.len()];
+    for triangle in data.indices.chunks_exact(3) {
+        let normal = face_normal(data, triangle);
+        for &i in triangle {
+            normals[group[i as usize]] += normal;
+        }
+    }
+
+    data.normals = group.iter()
+        .map(|&group| normals[group].normalize_or_zero().to_array())
+        .collect();
+}
+
+/// Gives every triangle its own vertices facing the same way as the triangle. Use `weld`
+/// afterwards to merge the corners that still match.
+pub fn compute_flat_normals(data: &mut MeshData) {
+    unweld(data);
+
+    for (triangle, corners) in data.indices.chunks_exact(3).zip(0..) {
+        // Adding zero turns -0.0 into 0.0, so `weld` can merge corners of axis-aligned faces.
+        let normal = (face_normal(data, triangle).normalize_or_zero() + Vec3::ZERO).to_array();
+        data.normals[corners * 3..corners * 3 + 3].fill(normal);
+    }
+}
+
+/// MikkTSpace tangents, with the bitangent sign in `w`. Needs normals and UVs, and leaves the
+/// mesh welded since corners sharing a vertex can end up with different tangents.
+pub fn generate_tangents(data: &mut MeshData) -> Result<()> {
+    unweld(data);
+
+    let mut geometry = TangentGeometry {
+        data,
+        tangents: vec![[0.0; 4]; data.indices.len()],
+    };
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        bail!("unable to generate tangents, the mesh has no usable UVs");
+    }
+
+    data.tangents = Some(geometry.tangents);
+    weld(data);
+
+    Ok(())
+}
+
+struct TangentGeometry<'a> {
+    data: &'a MeshData,
+    tangents: Vec<[f32; 4]>,
+}
+
+impl TangentGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.data.indices[face * 3 + vert] as usize
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.data.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.data.positions[self.vertex(face, vert)]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.data.normals[self.vertex(face, vert)]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.data.tex_coords[self.vertex(face, vert)]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent;
+    }
+}
+
+/// Merges vertices whose attributes are all bit-for-bit identical.
+pub fn weld(data: &mut MeshData) {
+    let stride = data.layout().array_stride() as usize;
+    let bytes = data.vertex_bytes();
+
+    let mut lookup = HashMap::new();
+    let mut unique = Vec::new();
+    let remap: Vec<u32> = bytes.chunks_exact(stride).zip(0..)
+        .map(|(vertex, i)| *lookup.entry(vertex).or_insert_with(|| {
+            unique.push(i);
+            unique.len() as u32 - 1
+        }))
+        .collect();
+
+    let indices = data.indices.iter().map(|&i| remap[i as usize]).collect();
+    *data = MeshData {
+        indices,
+        ..select(data, &unique)
+    };
+}
+
+/// Reorders triangles so vertices are reused while still in the post-transform cache, using
+/// Tipsify (Sander et al. 2007).
+pub fn optimize_vertex_cache(data: &mut MeshData) {
+    let vertex_count = data.vertex_count();
+    let triangle_count = data.indices.len() / 3;
+
+    // Triangles using each vertex, as ranges into `adjacency`.
+    let mut offsets = vec![0; vertex_count + 1];
+    for &i in &data.indices {
+        offsets[i as usize + 1] += 1;
+    }
+    for i in 0..vertex_count {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut live: Vec<usize> = (0..vertex_count).map(|i| offsets[i + 1] - offsets[i]).collect();
+    let mut adjacency = vec![0; data.indices.len()];
+    let mut filled = offsets.clone();
+    for (i, &vertex) in data.indices.iter().enumerate() {
+        adjacency[filled[vertex as usize]] = i / 3;
+        filled[vertex as usize] += 1;
+    }
+
+    let mut cache_time = vec![0; vertex_count];
+    let mut time = CACHE_SIZE + 1;
+    let mut emitted = vec![false; triangle_count];
+    let mut dead_ends = Vec::new();
+    let mut cursor = 0;
+    let mut indices = Vec::with_capacity(data.indices.len());
+
+    let mut fanning = (0..vertex_count).find(|&i| live[i] > 0);
+    while let Some(vertex) = fanning {
+        let mut candidates = Vec::new();
+        for &triangle in &adjacency[offsets[vertex]..offsets[vertex + 1]] {
+            if emitted[triangle] {
+                continue;
+            }
+            emitted[triangle] = true;
+
+            for &i in &data.indices[triangle * 3..triangle * 3 + 3] {
+                let i = i as usize;
+                indices.push(i as u32);
+                dead_ends.push(i);
+                candidates.push(i);
+                live[i] -= 1;
+
+                if time - cache_time[i] > CACHE_SIZE {
+                    cache_time[i] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // Prefer the candidate still in the cache that's been there longest, as long as its
+        // remaining triangles won't push it out. Without one, restart from the dead-end stack.
+        fanning = candidates.into_iter()
+            .filter(|&i| live[i] > 0)
+            .map(|i| {
+                let age = time - cache_time[i];
+                (if age + 2 * live[i] <= CACHE_SIZE { age } else { 0 }, i)
+            })
+            .filter(|&(priority, _)| priority > 0)
+            .max_by_key(|&(priority, _)| priority)
+            .map(|(_, i)| i)
+            .or_else(|| {
+                while let Some(i) = dead_ends.pop() {
+                    if live[i] > 0 {
+                        return Some(i);
+                    }
+                }
+                while cursor < vertex_count {
+                    cursor += 1;
+                    if live[cursor - 1] > 0 {
+                        return Some(cursor - 1);
+                    }
+                }
+                None
+            });
+    }
+
+    data.indices = indices;
+}
+
+/// Reorders clusters of triangles so those on the outside of the mesh are drawn first and
+/// hide what's behind them (Sander et al. 2007). Clusters are cut wherever the triangle order
+/// jumps, so run this after `optimize_vertex_cache` to keep its cache efficiency.
+pub fn optimize_overdraw(data: &mut MeshData) {
+    let mut clusters: Vec<Vec<u32>> = Vec::new();
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE);
+    for triangle in data.indices.chunks_exact(3) {
+        let mut misses = 0;
+        for &i in triangle {
+            if !cache.contains(&i) {
+                misses += 1;
+                if cache.len() == CACHE_SIZE {
+                    cache.remove(0);
+                }
+                cache.push(i);
+            }
+        }
+
+        match clusters.last_mut() {
+            Some(cluster) if misses < 3 => cluster.extend_from_slice(triangle),
+            _ => clusters.push(triangle.to_vec()),
+        }
+    }
+
+    let centroid = |indices: &[u32]| {
+        let (sum, area) = indices.chunks_exact(3).fold((Vec3::ZERO, 0.0), |(sum, area), triangle| {
+            let triangle_area = face_normal(data, triangle).length();
+            let center = triangle.iter().map(|&i| Vec3::from(data.positions[i as usize])).sum::<Vec3>() / 3.0;
+            (sum + center * triangle_area, area + triangle_area)
+        });
+        if area > 0.0 { sum / area } else { Vec3::ZERO }
+    };
+
+    let mesh_centroid = centroid(&data.indices);
+    let mut sorted: Vec<(f32, Vec<u32>)> = clusters.into_iter()
+        .map(|cluster| {
+            let normal = cluster.chunks_exact(3).map(|triangle| face_normal(data, triangle)).sum::<Vec3>().normalize_or_zero();
+            ((centroid(&cluster) - mesh_centroid).dot(normal), cluster)
+        })
+        .collect();
+    sorted.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    data.indices = sorted.into_iter().flat_map(|(_, cluster)| cluster).collect();
+}
+
+/// Reorders vertices by first use so the vertex buffer is read front to back, dropping any
+/// that no triangle uses.
+pub fn optimize_vertex_fetch(data: &mut MeshData) {
+    let mut remap = vec![u32::MAX; data.vertex_count()];
+    let mut order = Vec::new();
+    let indices = data.indices.iter()
+        .map(|&i| {
+            if remap[i as usize] == u32::MAX {
+                remap[i as usize] = order.len() as u32;
+                order.push(i);
+            }
+            remap[i as usize]
+        })
+        .collect();
+
+    *data = MeshData {
+        indices,
+        ..select(data, &order)
+    };
+}
+
+/// Splits the mesh so every index has its own vertex.
+fn unweld(data: &mut MeshData) {
+    *data = MeshData {
+        indices: (0..data.indices.len() as u32).collect(),
+        ..select(data, &data.indices)
+    };
+}
+
+/// The given vertices of `data`, without indices.
+fn select(data: &MeshData, vertices: &[u32]) -> MeshData {
+    fn pick<T: Copy>(stream: &[T], vertices: &[u32]) -> Vec<T> {
+        vertices.iter().map(|&i| stream[i as usize]).collect()
+    }
+
+    MeshData {
+        positions: pick(&data.positions, vertices),
+        tex_coords: pick(&data.tex_coords, vertices),
+        normals: pick(&data.normals, vertices),
+        tangents: data.tangents.as_deref().map(|stream| pick(stream, vertices)),
+        colors: data.colors.as_deref().map(|stream| pick(stream, vertices)),
+        tex_coords1: data.tex_coords1.as_deref().map(|stream| pick(stream, vertices)),
+        joints: data.joints.as_deref().map(|stream| pick(stream, vertices)),
+        weights: data.weights.as_deref().map(|stream| pick(stream, vertices)),
+        indices: Vec::new(),
+    }
+}
+
+/// Counter-clockwise normal of `triangle` with a length of twice its area.
+fn face_normal(data: &MeshData, triangle: &[u32]) -> Vec3 {
+    let [a, b, c] = [0, 1, 2].map(|corner| Vec3::from(data.positions[triangle[corner] as usize]));
+    (b - a).cross(c - a)
+}
+
+/// Symmetric 4x4 error quadric, storing the upper triangle.
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    planes: [f64; 10],
+    /// Total area of the triangles summed into `planes`.
+    area: f64,
+}
+
+impl Quadric {
+    /// Squared distance to the plane through `a`, `b` and `c`, weighted by the triangle's area.
+    fn from_triangle(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        let normal = (b - a).cross(c - a).as_dvec3();
+        let area = normal.length() * 0.5;
+        if area == 0.0 {
+            return Self::default();
+        }
+
+        let normal = normal.normalize();
+        let d = -normal.dot(a.as_dvec3());
+        let [x, y, z] = normal.to_array();
+
+        Self {
+            planes: [x * x, x * y, x * z, x * d, y * y, y * z, y * d, z * z, z * d, d * d].map(|q| q * area),
+            area,
+        }
+    }
+
+    fn add(&self, other: &Quadric) -> Self {
+        let mut planes = self.planes;
+        planes.iter_mut().zip(other.planes).for_each(|(a, b)| *a += b);
+        Self {
+            planes,
+            area: self.area + other.area,
+        }
+    }
+
+    /// Squared distance from `position` to the planes, averaged by area so it doesn't grow
+    /// with the size of the mesh.
+    fn error(&self, position: Vec3) -> f64 {
+        if self.area == 0.0 {
+            return 0.0;
+        }
+
+        let [xx, xy, xz, xd, yy, yz, yd, zz, zd, dd] = self.planes;
+        let [x, y, z] = position.as_dvec3().to_array();
+
+        let error = xx * x * x + 2.0 * xy * x * y + 2.0 * xz * x * z + 2.0 * xd * x
+            + yy * y * y + 2.0 * yz * y * z + 2.0 * yd * y
+            + zz * z * z + 2.0 * zd * z + dd;
+        error.max(0.0) / self.area
+    }
+}
+
+/// Collapsing vertex `from` into `to`, ordered so the cheapest comes out of a `BinaryHeap` first.
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    /// `stamps` of both vertices when the cost was computed.
+    stamps: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Quadric error simplification (Garland & Heckbert 1997), collapsing vertices into their
+/// neighbours and returning new indices into the same vertices. Vertices on open borders and
+/// attribute seams stay put, so the outline and UV layout hold up.
+///
+/// Stops at `target_index_count` or before the error, relative to the size of the mesh,
+/// would exceed `max_error`.
+pub fn simplify(data: &MeshData, target_index_count: usize, max_error: f32) -> Vec<u32> {
+    let positions: Vec<Vec3> = data.positions.iter().copied().map(Vec3::from).collect();
+    let vertex_count = positions.len();
+
+    // Vertices sharing a position, and edges between them used by only one triangle.
+    let mut groups = HashMap::new();
+    let group: Vec<usize> = data.positions.iter()
+        .map(|position| {
+            let next = groups.len();
+            *groups.entry(position.map(f32::to_bits)).or_insert(next)
+        })
+        .collect();
+    let mut group_sizes = vec![0; groups.len()];
+    group.iter().for_each(|&group| group_sizes[group] += 1);
+
+    let mut edges = HashMap::new();
+    for triangle in data.indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let (a, b) = (group[triangle[corner] as usize], group[triangle[(corner + 1) % 3] as usize]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+
+    let mut locked: Vec<bool> = group.iter().map(|&group| group_sizes[group] > 1).collect();
+    let mut border_groups = vec![false; groups.len()];
+    for (&(a, b), _) in edges.iter().filter(|(_, &count)| count == 1) {
+        border_groups[a] = true;
+        border_groups[b] = true;
+    }
+    locked.iter_mut().zip(&group).for_each(|(locked, &group)| *locked |= border_groups[group]);
+
+    let mut triangles: Vec<[usize; 3]> = data.indices.chunks_exact(3)
+        .map(|triangle| [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize])
+        .collect();
+    let mut alive = vec![true; triangles.len()];
+    let mut triangle_count = triangles.len();
+
+    let mut quadrics = vec![Quadric::default(); vertex_count];
+    let mut vertex_triangles = vec![Vec::new(); vertex_count];
+    for (t, &[a, b, c]) in triangles.iter().enumerate() {
+        let quadric = Quadric::from_triangle(positions[a], positions[b], positions[c]);
+        for i in [a, b, c] {
+            quadrics[i] = quadrics[i].add(&quadric);
+            vertex_triangles[i].push(t);
+        }
+    }
+
+    let aabb = Aabb::from_points(positions.iter().copied());
+    let scale = (aabb.max - aabb.min).length().max(f32::EPSILON) as f64;
+    let max_cost = (max_error as f64 * scale).powi(2);
+
+    let mut stamps = vec![0u32; vertex_count];
+    let mut removed = vec![false; vertex_count];
+    let mut heap = std::collections::BinaryHeap::new();
+    let candidate = |from: usize, to: usize, quadrics: &[Quadric], stamps: &[u32]| Collapse {
+        cost: quadrics[from].add(&quadrics[to]).error(positions[to]),
+        from,
+        to,
+        stamps: (stamps[from], stamps[to]),
+    };
+
+    for &[a, b, c] in &triangles {
+        for (from, to) in [(a, b), (b, a), (b, c), (c, b), (c, a), (a, c)] {
+            if !locked[from] {
+                heap.push(candidate(from, to, &quadrics, &stamps));
+            }
+        }
+    }
+
+    while triangle_count * 3 > target_index_count {
+        let Some(collapse) = heap.pop() else { break };
+        let (from, to) = (collapse.from, collapse.to);
+        if removed[from] || removed[to] {
+            continue;
+        }
+
+        if collapse.stamps != (stamps[from], stamps[to]) {
+            let connected = vertex_triangles[from].iter()
+                .any(|&t| alive[t] && triangles[t].contains(&to));
+            if connected {
+                heap.push(candidate(from, to, &quadrics, &stamps));
+            }
+            continue;
+        }
+
+        if collapse.cost > max_cost {
+            break;
+        }
+
+        // Moving `from` must not fold any of its other triangles over.
+        let flips = vertex_triangles[from].iter()
+            .filter(|&&t| alive[t] && !triangles[t].contains(&to))
+            .any(|&t| {
+                let corners = triangles[t].map(|i| positions[i]);
+                let moved = triangles[t].map(|i| if i == from { positions[to] } else { positions[i] });
+                let before = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+                let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+                before.dot(after) <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        removed[from] = true;
+        quadrics[to] = quadrics[to].add(&quadrics[from]);
+        for t in std::mem::take(&mut vertex_triangles[from]) {
+            if !alive[t] {
+                continue;
+            }
+
+            if triangles[t].contains(&to) {
+                alive[t] = false;
+                triangle_count -= 1;
+            } else {
+                triangles[t].iter_mut().filter(|i| **i == from).for_each(|i| *i = to);
+                vertex_triangles[to].push(t);
+            }
+        }
+        vertex_triangles[to].retain(|&t| alive[t]);
+
+        let neighbours: Vec<usize> = vertex_triangles[to].iter()
+            .flat_map(|&t| triangles[t])
+            .collect();
+        for &i in &neighbours {
+            stamps[i] += 1;
+        }
+        for &i in neighbours.iter().filter(|&&i| i != to) {
+            if !locked[i] {
+                heap.push(candidate(i, to, &quadrics, &stamps));
+            }
+            if !locked[to] {
+                heap.push(candidate(to, i, &quadrics, &stamps));
+            }
+        }
+    }
+
+    triangles.iter().zip(&alive)
+        .filter(|(_, &alive)| alive)
+        .flat_map(|(triangle, _)| triangle.map(|i| i as u32))
+        .collect()
+}
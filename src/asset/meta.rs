@@ -0,0 +1,35 @@
+use anyhow::{Context as _, Result};
+use serde::de::DeserializeOwned;
+
+use super::sources::{self, AssetSources};
+
+/// Import settings can be stored next to an asset in a YAML sidecar named after it, e.g.
+/// `res/textures/stone_bricks.jpg.meta`:
+///
+/// ```yaml
+/// color_space: srgb
+/// wrap: repeat
+/// anisotropy: 8
+/// max_size: 1024
+/// ```
+///
+/// A sidecar takes priority over settings passed in code or through the manifest.
+pub fn meta_path(file_path: &str) -> String {
+    format!("{}.meta", file_path)
+}
+
+/// Returns `None` if `file_path` has no sidecar, and an error if it has one that can't be read
+/// or doesn't parse.
+pub async fn read_settings<S: DeserializeOwned>(sources: &AssetSources, file_path: &str) -> Result<Option<S>> {
+    let meta_path = meta_path(file_path);
+    let bytes = match sources.read(&meta_path).await {
+        Ok(bytes) => bytes,
+        Err(e) if sources::is_not_found(&e) => return Ok(None),
+        Err(e) => return Err(e.context(format!("Unable to read import settings: {}", meta_path))),
+    };
+
+    let settings = serde_yaml::from_slice(&bytes)
+        .with_context(|| format!("Invalid import settings: {}", meta_path))?;
+
+    Ok(Some(settings))
+}
@@ -1,24 +1,79 @@
 use std::sync::Arc;
 
+use anyhow::{bail, Result};
 use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::engine::context::Context;
 
+use sources::AssetSources;
+
 pub mod texture;
+pub mod cube_texture;
+pub mod atlas;
 pub mod mesh;
+pub mod mesh_processing;
 
 pub mod pools;
 
 pub mod asset_manager;
 pub mod primitives;
+pub mod loaders;
+pub mod watcher;
+pub mod manifest;
+pub mod meta;
+pub mod cache;
+pub mod sources;
 
 pub mod handle;
+pub mod events;
+pub mod load_group;
+pub mod loader;
 
 pub use texture::Texture;
+pub use cube_texture::CubeTexture;
 pub use mesh::Mesh;
 
+/// Splits a labeled path to an asset stored inside another file, e.g. `scene.gltf#mesh0/primitive1`
+/// or `ui.atlas#button`, into the file path and the label.
+pub fn split_label(file_path: &str) -> (&str, Option<&str>) {
+    match file_path.split_once('#') {
+        Some((file_path, label)) => (file_path, Some(label)),
+        None => (file_path, None),
+    }
+}
 
 #[async_trait]
-pub trait Asset {
-    async fn load(context: &Context, file_path: &str) -> Arc<Self>;
+pub trait Asset: Send + Sync + 'static {
+    /// Name used for the `type` field of manifest entries.
+    const TYPE_NAME: &'static str;
+
+    /// Import options, read from the asset manifest or a `.meta` sidecar.
+    /// Serialized into the cache key of cooked assets, see `cache::AssetCache`.
+    type Settings: Default + Clone + Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    /// Reads the asset's bytes from `sources`, which resolves `file_path` to the right `AssetSource`.
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, settings: &Self::Settings) -> Result<Arc<Self>>;
+
+    /// Whether `file_path` can be loaded in two steps, `cook` then `from_cooked`, so the
+    /// `AssetCache` can keep the cooked form and skip decoding next time.
+    fn cookable(_file_path: &str) -> bool {
+        false
+    }
+
+    /// Decodes and processes `bytes`, the contents of `file_path`, into a binary form that
+    /// `from_cooked` can upload as-is.
+    fn cook(file_path: &str, _bytes: &[u8], _settings: &Self::Settings) -> Result<Vec<u8>> {
+        bail!("{}: {} assets can't be cooked", file_path, Self::TYPE_NAME)
+    }
+
+    fn from_cooked(_context: &Context, _cooked: &[u8], _settings: &Self::Settings) -> Result<Arc<Self>> {
+        bail!("{} assets can't be cooked", Self::TYPE_NAME)
+    }
+
+    /// Approximate GPU memory used by the asset, counted against `AssetPool` budgets. Assets of
+    /// size 0 are never evicted.
+    fn gpu_size(&self) -> u64 {
+        0
+    }
 }
\ No newline at end of file
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use crate::{asset::{cube_texture::{CubeTexture, CubeTextureSettings}, texture::Texture}, engine::context::Context};
+
+use super::AssetPool;
+
+impl AssetPool<CubeTexture> {
+    pub fn new(context: &Context) -> Self {
+        let black = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba([0, 0, 0, 255])));
+        let default = Arc::new(CubeTexture::from_faces(context, &vec![black; 6], &CubeTextureSettings::default()).unwrap());
+
+        let error_faces = vec![AssetPool::<Texture>::error_image(); 6];
+        let error = Arc::new(CubeTexture::from_faces(context, &error_faces, &CubeTextureSettings::default()).unwrap());
+
+        Self::with_placeholders(default, error)
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::asset::handle::AssetId;
 use crate::asset::mesh::Mesh;
-use crate::asset::primitives::quad;
+use crate::asset::primitives::PrimitiveMesh;
 use crate::engine::context::Context;
 
 use super::AssetPool;
@@ -11,20 +12,21 @@ use super::AssetPool;
 impl AssetPool<Mesh> {
     pub fn new(context: &Context) -> Self {
         let meshes = Self::load_primitives(context);
-        let default = meshes.get(&0).unwrap().clone();
+        let default = meshes[&PrimitiveMesh::Quad.asset_id()].clone();
+        // A cube of its own, so meshes that failed to load don't look like ones still loading.
+        let error = Arc::new(Mesh::from_primitive(context, &PrimitiveMesh::Cube.generate()).unwrap());
 
-        Self {
-            assets: meshes,
-            default,
+        let mut pool = Self::with_placeholders(default, error);
+        for (asset_id, mesh) in meshes {
+            pool.insert(asset_id, mesh);
         }
-    }
-
-    fn load_primitives(context: &Context) -> HashMap<usize, Arc<Mesh>> {
-        let quad = Arc::new(Mesh::new(context, quad::VERTICES, quad::INDICES));
 
-        let mut meshes = HashMap::new();
-        meshes.insert(0, quad);
+        pool
+    }
 
-        meshes
+    fn load_primitives(context: &Context) -> HashMap<AssetId, Arc<Mesh>> {
+        PrimitiveMesh::ALL.into_iter()
+            .map(|primitive_mesh| (primitive_mesh.asset_id(), Arc::new(Mesh::from_primitive(context, &primitive_mesh.generate()).unwrap())))
+            .collect()
     }
 }
\ No newline at end of file
@@ -1,28 +1,313 @@
 pub mod mesh_pool;
 pub mod texture_pool;
+pub mod cube_texture_pool;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{any::Any, collections::{HashMap, HashSet}, sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}}};
 
-use super::Asset;
+use super::{Asset, handle::AssetId};
+
+struct PoolEntry<T> {
+    asset: Arc<T>,
+    /// `Asset::gpu_size`, counted against the budget.
+    size: u64,
+    /// Value of `AssetPool::clock` when the asset was last returned by `get`.
+    last_used: AtomicU64,
+    /// Loaded from a path, so it can be evicted and loaded again.
+    reloadable: bool,
+}
 
 pub struct AssetPool<T: Asset> {
-    assets: HashMap<usize, Arc<T>>,
+    assets: HashMap<AssetId, PoolEntry<T>>,
     default: Arc<T>,
+    error: Arc<T>,
+
+    budget: Option<u64>,
+    usage: u64,
+    clock: AtomicU64,
+    evicted: HashSet<AssetId>,
+    /// Evicted assets that were asked for since the last `take_reload_requests`.
+    reload_requests: Mutex<HashSet<AssetId>>,
 }
 
 impl<T: Asset> AssetPool<T> {
-    pub fn get(&self, id: usize) -> Arc<T> {
+    /// Creates an empty pool that falls back to `default` while loading and after a failed load.
+    pub fn with_default(default: Arc<T>) -> Self {
+        Self::with_placeholders(default.clone(), default)
+    }
+
+    /// Creates an empty pool showing `error` in place of assets that failed to load.
+    pub fn with_placeholders(default: Arc<T>, error: Arc<T>) -> Self {
+        Self {
+            assets: HashMap::new(),
+            default,
+            error,
+
+            budget: None,
+            usage: 0,
+            clock: AtomicU64::new(0),
+            evicted: HashSet::new(),
+            reload_requests: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The asset shown while loading.
+    pub fn get_default(&self) -> Arc<T> {
+        self.default.clone()
+    }
+
+    /// Returns the default for assets that are loading or were evicted, and requests evicted
+    /// ones to be loaded again.
+    pub fn get(&self, id: AssetId) -> Arc<T> {
         match self.assets.get(&id) {
-            Some(asset) => return asset.clone(),
-            None => return self.default.clone()
+            Some(entry) => {
+                entry.last_used.store(self.clock.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+                entry.asset.clone()
+            },
+            None => {
+                if self.evicted.contains(&id) {
+                    self.reload_requests.lock().unwrap().insert(id);
+                }
+                self.default.clone()
+            },
         }
     }
 
-    pub fn insert(&mut self, id: usize, asset: Arc<T>) {
-        self.assets.insert(id, asset);
+    /// Adds an asset that can't be loaded again, so it's never evicted.
+    pub fn insert(&mut self, id: AssetId, asset: Arc<T>) {
+        let size = asset.gpu_size();
+        self.insert_entry(id, asset, size, false);
+    }
+
+    /// Adds an asset loaded from a path, which may be evicted when over budget.
+    pub fn insert_loaded(&mut self, id: AssetId, asset: Arc<T>) {
+        let size = asset.gpu_size();
+        self.insert_entry(id, asset, size, true);
+    }
+
+    fn insert_entry(&mut self, id: AssetId, asset: Arc<T>, size: u64, reloadable: bool) {
+        let entry = PoolEntry {
+            asset,
+            size,
+            last_used: AtomicU64::new(self.clock.fetch_add(1, Ordering::Relaxed)),
+            reloadable,
+        };
+
+        self.usage += size;
+        if let Some(previous) = self.assets.insert(id, entry) {
+            self.usage -= previous.size;
+        }
+        self.evicted.remove(&id);
+    }
+
+    pub fn remove(&mut self, id: AssetId) -> Option<Arc<T>> {
+        self.evicted.remove(&id);
+        let entry = self.assets.remove(&id)?;
+        self.usage -= entry.size;
+
+        Some(entry.asset)
+    }
+
+    /// Points `id` at the error placeholder so a failed load is distinguishable from one still in flight.
+    pub fn insert_error(&mut self, id: AssetId) {
+        // The placeholder is shared, so it doesn't count against the budget.
+        self.insert_entry(id, self.error.clone(), 0, false);
+    }
+
+    /// Approximate GPU memory the pool may use before evicting, `None` for no limit.
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+    }
+
+    /// Approximate GPU memory used by the pool's assets, excluding the placeholders.
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    /// Evicts least recently used assets until the pool fits its budget. Only reloadable
+    /// assets nothing outside the pool holds on to are evicted, so the pool may stay over.
+    /// Returns the evicted ids.
+    pub fn enforce_budget(&mut self) -> Vec<AssetId> {
+        let Some(budget) = self.budget else { return Vec::new() };
+
+        let mut candidates: Vec<(u64, AssetId)> = self.assets.iter()
+            .filter(|(_, entry)| entry.reloadable && entry.size > 0 && Arc::strong_count(&entry.asset) == 1)
+            .map(|(&id, entry)| (entry.last_used.load(Ordering::Relaxed), id))
+            .collect();
+        candidates.sort_unstable();
+
+        let mut evicted = Vec::new();
+        for (_, id) in candidates {
+            if self.usage <= budget {
+                break;
+            }
+            self.remove(id);
+            self.evicted.insert(id);
+            evicted.push(id);
+        }
+
+        evicted
+    }
+
+    /// Evicted assets that `get` was called for since the last call. They stop being requested
+    /// until they're inserted again.
+    pub fn take_reload_requests(&mut self) -> Vec<AssetId> {
+        let requests: Vec<AssetId> = self.reload_requests.get_mut().unwrap().drain().collect();
+        for id in &requests {
+            self.evicted.remove(id);
+        }
+
+        requests
     }
 
     pub fn len(&self) -> usize {
         self.assets.len()
     }
-}
\ No newline at end of file
+}
+
+/// Type-erased `AssetPool<T>`, letting the `AssetManager` keep one pool per registered asset type.
+pub(crate) trait AnyPool: Send + Sync {
+    /// `asset` must be a boxed `Arc<T>` matching the pool's type, loaded from a path.
+    fn insert_any(&mut self, id: AssetId, asset: Box<dyn Any + Send + Sync>);
+    fn insert_error(&mut self, id: AssetId);
+    fn remove(&mut self, id: AssetId);
+    fn enforce_budget(&mut self) -> Vec<AssetId>;
+    fn take_reload_requests(&mut self) -> Vec<AssetId>;
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Asset> AnyPool for AssetPool<T> {
+    fn insert_any(&mut self, id: AssetId, asset: Box<dyn Any + Send + Sync>) {
+        let asset = asset.downcast::<Arc<T>>()
+            .unwrap_or_else(|_| panic!("Asset type mismatch for {}", T::TYPE_NAME));
+        AssetPool::insert_loaded(self, id, *asset);
+    }
+
+    fn insert_error(&mut self, id: AssetId) {
+        AssetPool::insert_error(self, id);
+    }
+
+    fn remove(&mut self, id: AssetId) {
+        AssetPool::remove(self, id);
+    }
+
+    fn enforce_budget(&mut self) -> Vec<AssetId> {
+        AssetPool::enforce_budget(self)
+    }
+
+    fn take_reload_requests(&mut self) -> Vec<AssetId> {
+        AssetPool::take_reload_requests(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{bail, Result};
+    use async_trait::async_trait;
+
+    use crate::{asset::sources::AssetSources, engine::context::Context};
+
+    use super::*;
+
+    /// Asset reporting its value as its GPU size.
+    struct Sized(u64);
+
+    #[async_trait]
+    impl Asset for Sized {
+        const TYPE_NAME: &'static str = "sized";
+        type Settings = ();
+
+        async fn load(_context: &Context, _sources: &AssetSources, file_path: &str, _settings: &()) -> Result<Arc<Self>> {
+            bail!("{} can't be loaded", file_path)
+        }
+
+        fn gpu_size(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn pool(budget: u64) -> AssetPool<Sized> {
+        let mut pool = AssetPool::with_placeholders(Arc::new(Sized(0)), Arc::new(Sized(1000)));
+        pool.set_budget(Some(budget));
+        pool
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let mut pool = pool(250);
+        let ids: Vec<AssetId> = (0..4).map(|_| AssetId::random()).collect();
+        for &id in &ids {
+            pool.insert_loaded(id, Arc::new(Sized(100)));
+        }
+        assert_eq!(pool.usage(), 400);
+
+        // Using the first asset makes the second the least recently used.
+        pool.get(ids[0]);
+        let evicted = pool.enforce_budget();
+
+        assert_eq!(evicted, [ids[1], ids[2]]);
+        assert_eq!(pool.usage(), 200);
+        assert!(pool.enforce_budget().is_empty());
+    }
+
+    #[test]
+    fn keeps_assets_that_are_held_or_not_reloadable() {
+        let mut pool = pool(0);
+        let (held, added, loaded) = (AssetId::random(), AssetId::random(), AssetId::random());
+        pool.insert_loaded(held, Arc::new(Sized(100)));
+        pool.insert(added, Arc::new(Sized(100)));
+        pool.insert_loaded(loaded, Arc::new(Sized(100)));
+
+        let _held = pool.get(held);
+        assert_eq!(pool.enforce_budget(), [loaded]);
+        assert_eq!(pool.usage(), 200);
+    }
+
+    #[test]
+    fn error_placeholders_are_free() {
+        let mut pool = pool(0);
+        let id = AssetId::random();
+        pool.insert_error(id);
+
+        assert_eq!(pool.usage(), 0);
+        assert!(pool.enforce_budget().is_empty());
+        assert_eq!(pool.get(id).0, 1000);
+    }
+
+    #[test]
+    fn requests_evicted_assets_once_used_again() {
+        let mut pool = pool(0);
+        let id = AssetId::random();
+        pool.insert_loaded(id, Arc::new(Sized(100)));
+        pool.enforce_budget();
+
+        assert!(pool.take_reload_requests().is_empty());
+        assert_eq!(pool.get(id).0, 0);
+        assert_eq!(pool.take_reload_requests(), [id]);
+
+        // Requested once, until it's inserted and evicted again.
+        pool.get(id);
+        assert!(pool.take_reload_requests().is_empty());
+    }
+
+    #[test]
+    fn replacing_an_asset_updates_usage() {
+        let mut pool = pool(1000);
+        let id = AssetId::random();
+        pool.insert_loaded(id, Arc::new(Sized(100)));
+        pool.insert_loaded(id, Arc::new(Sized(300)));
+        assert_eq!(pool.usage(), 300);
+
+        pool.remove(id);
+        assert_eq!(pool.usage(), 0);
+    }
+}
@@ -1,16 +1,27 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
-use crate::{asset::texture::Texture, engine::context::Context};
+use crate::{asset::texture::{Texture, TextureSettings}, engine::context::Context};
 
 use super::AssetPool;
 
 impl AssetPool<Texture> {
     pub fn new(context: &Context) -> Self {
-        let default = Arc::new(Texture::from_bytes(context, include_bytes!("../../../res/textures/default_texture.png"), false).unwrap());
+        let default = Arc::new(Texture::from_bytes(context, include_bytes!("../../../res/textures/default_texture.png"), &TextureSettings::default()).unwrap());
+        let error = Arc::new(Texture::from_image(context, &Self::error_image(), &TextureSettings::default()).unwrap());
 
-        Self {
-            assets: HashMap::new(),
-            default,
-        }
+        Self::with_placeholders(default, error)
+    }
+
+    /// Magenta and black checkerboard shown in place of textures that failed to load.
+    pub(super) fn error_image() -> image::DynamicImage {
+        let image = image::RgbaImage::from_fn(64, 64, |x, y| {
+            if (x / 8 + y / 8) % 2 == 0 {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+
+        image::DynamicImage::ImageRgba8(image)
     }
 }
\ No newline at end of file
@@ -0,0 +1,78 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{vec2, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex, around_y, sphere::latitude};
+
+/// A cylinder with hemispherical ends along the Y axis. `v` is spread by arc length so the
+/// texture isn't stretched over the ends.
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule {
+    radius: f32,
+    height: f32,
+    sectors: u32,
+    rings: u32,
+}
+
+impl Default for Capsule {
+    fn default() -> Self {
+        Self {
+            radius: 0.25,
+            height: 1.0,
+            sectors: 32,
+            rings: 8,
+        }
+    }
+}
+
+impl Capsule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Total height including the ends, at least twice the radius.
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Number of segments around the side, and from each pole to the cylinder.
+    pub fn with_segments(mut self, sectors: u32, rings: u32) -> Self {
+        self.sectors = sectors.max(3);
+        self.rings = rings.max(1);
+        self
+    }
+}
+
+impl Primitive for Capsule {
+    fn generate(&self) -> PrimitiveData {
+        let half_cylinder = (self.height * 0.5 - self.radius).max(0.0);
+        let length = 2.0 * (FRAC_PI_2 * self.radius + half_cylinder);
+
+        // Rows `0..=rings` make up the top cap and the rest the bottom one, the row between
+        // them being the cylinder.
+        let mut data = PrimitiveData::new();
+        data.grid(self.sectors, 2 * self.rings + 1, |column, row| {
+            let (polar, offset, distance) = if row <= self.rings {
+                let polar = row as f32 / self.rings as f32 * FRAC_PI_2;
+                (polar, half_cylinder, polar * self.radius)
+            } else {
+                let polar = (row - 1) as f32 / self.rings as f32 * FRAC_PI_2;
+                (polar, -half_cylinder, polar * self.radius + 2.0 * half_cylinder)
+            };
+
+            let u = column as f32 / self.sectors as f32;
+            let normal = latitude(around_y(u), polar);
+            let position = normal * self.radius + Vec3::Y * offset;
+
+            vertex(position, vec2(u, distance / length), normal)
+        });
+
+        data
+    }
+}
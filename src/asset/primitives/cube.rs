@@ -0,0 +1,63 @@
+use glam::{vec2, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex};
+
+/// A box with separate vertices per face, each face mapped to the whole texture.
+#[derive(Clone, Copy, Debug)]
+pub struct Cube {
+    size: Vec3,
+    subdivisions: u32,
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Self {
+            size: Vec3::ONE,
+            subdivisions: 1,
+        }
+    }
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_size(mut self, size: Vec3) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Number of quads along each edge of a face.
+    pub fn with_subdivisions(mut self, subdivisions: u32) -> Self {
+        self.subdivisions = subdivisions.max(1);
+        self
+    }
+}
+
+impl Primitive for Cube {
+    fn generate(&self) -> PrimitiveData {
+        // Normal, then the directions `u` and `v` run in across the face.
+        const FACES: [[Vec3; 3]; 6] = [
+            [Vec3::X, Vec3::NEG_Z, Vec3::NEG_Y],
+            [Vec3::NEG_X, Vec3::Z, Vec3::NEG_Y],
+            [Vec3::Y, Vec3::X, Vec3::Z],
+            [Vec3::NEG_Y, Vec3::X, Vec3::NEG_Z],
+            [Vec3::Z, Vec3::X, Vec3::NEG_Y],
+            [Vec3::NEG_Z, Vec3::NEG_X, Vec3::NEG_Y],
+        ];
+
+        let mut data = PrimitiveData::new();
+        let steps = self.subdivisions;
+        for [normal, u, v] in FACES {
+            data.grid(steps, steps, |column, row| {
+                let tex_coords = vec2(column as f32, row as f32) / steps as f32;
+                let position = normal * 0.5 + u * (tex_coords.x - 0.5) + v * (tex_coords.y - 0.5);
+
+                vertex(position * self.size, tex_coords, normal)
+            });
+        }
+
+        data
+    }
+}
@@ -0,0 +1,170 @@
+use glam::{vec2, vec3, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex, around_y};
+
+/// An upright cylinder along the Y axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder {
+    radius: f32,
+    height: f32,
+    sectors: u32,
+    stacks: u32,
+    caps: bool,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            sectors: 32,
+            stacks: 1,
+            caps: true,
+        }
+    }
+}
+
+impl Cylinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Number of segments around the side, and along its height.
+    pub fn with_segments(mut self, sectors: u32, stacks: u32) -> Self {
+        self.sectors = sectors.max(3);
+        self.stacks = stacks.max(1);
+        self
+    }
+
+    /// Leaves the ends open when `false`.
+    pub fn with_caps(mut self, caps: bool) -> Self {
+        self.caps = caps;
+        self
+    }
+}
+
+impl Primitive for Cylinder {
+    fn generate(&self) -> PrimitiveData {
+        let mut data = PrimitiveData::new();
+        side(&mut data, self.radius, self.radius, self.height, self.sectors, self.stacks);
+
+        if self.caps {
+            cap(&mut data, self.radius, self.height * 0.5, self.sectors, Vec3::Y);
+            cap(&mut data, self.radius, -self.height * 0.5, self.sectors, Vec3::NEG_Y);
+        }
+
+        data
+    }
+}
+
+/// An upright cone along the Y axis, with its tip at the top.
+#[derive(Clone, Copy, Debug)]
+pub struct Cone {
+    radius: f32,
+    height: f32,
+    sectors: u32,
+    stacks: u32,
+    cap: bool,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            sectors: 32,
+            stacks: 1,
+            cap: true,
+        }
+    }
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Radius of the base.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Number of segments around the side, and from the tip to the base.
+    pub fn with_segments(mut self, sectors: u32, stacks: u32) -> Self {
+        self.sectors = sectors.max(3);
+        self.stacks = stacks.max(1);
+        self
+    }
+
+    /// Leaves the base open when `false`.
+    pub fn with_cap(mut self, cap: bool) -> Self {
+        self.cap = cap;
+        self
+    }
+}
+
+impl Primitive for Cone {
+    fn generate(&self) -> PrimitiveData {
+        let mut data = PrimitiveData::new();
+        side(&mut data, 0.0, self.radius, self.height, self.sectors, self.stacks);
+
+        if self.cap {
+            cap(&mut data, self.radius, -self.height * 0.5, self.sectors, Vec3::NEG_Y);
+        }
+
+        data
+    }
+}
+
+/// The side of a truncated cone centred on the origin, wrapped once around by `u`.
+/// The tip of a cone gets one vertex per sector so each keeps its own normal.
+fn side(data: &mut PrimitiveData, top_radius: f32, bottom_radius: f32, height: f32, sectors: u32, stacks: u32) {
+    data.grid(sectors, stacks, |column, row| {
+        let tex_coords = vec2(column as f32 / sectors as f32, row as f32 / stacks as f32);
+        let outward = around_y(tex_coords.x);
+
+        let radius = top_radius + (bottom_radius - top_radius) * tex_coords.y;
+        let position = outward * radius + Vec3::Y * height * (0.5 - tex_coords.y);
+        let normal = (outward * height + Vec3::Y * (bottom_radius - top_radius)).normalize();
+
+        vertex(position, tex_coords, normal)
+    });
+}
+
+/// A disc at height `y` facing `normal`, which must be +Y or -Y. The texture is projected
+/// straight down onto it.
+fn cap(data: &mut PrimitiveData, radius: f32, y: f32, sectors: u32, normal: Vec3) {
+    let base = data.vertices.len() as u32;
+    let tex_coords = |offset: Vec3| vec2(0.5 + offset.x * 0.5, 0.5 + offset.z * 0.5 * normal.y);
+
+    data.vertices.push(vertex(vec3(0.0, y, 0.0), vec2(0.5, 0.5), normal));
+    for sector in 0..=sectors {
+        let outward = around_y(sector as f32 / sectors as f32);
+        data.vertices.push(vertex(outward * radius + Vec3::Y * y, tex_coords(outward), normal));
+    }
+
+    for sector in 0..sectors {
+        let (current, next) = (base + 1 + sector, base + 2 + sector);
+        if normal.y > 0.0 {
+            data.triangle([base, current, next]);
+        } else {
+            data.triangle([base, next, current]);
+        }
+    }
+}
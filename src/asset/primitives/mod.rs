@@ -1,5 +1,138 @@
 pub mod quad;
+pub mod cube;
+pub mod sphere;
+pub mod plane;
+pub mod cylinder;
+pub mod capsule;
+pub mod torus;
 
+use crate::engine::vertex::Vertex;
+
+use super::handle::AssetId;
+
+pub use cube::Cube;
+pub use sphere::{UvSphere, IcoSphere};
+pub use plane::Plane;
+pub use cylinder::{Cylinder, Cone};
+pub use capsule::Capsule;
+pub use torus::Torus;
+
+/// Meshes built into the `AssetManager` at their default sizes and resolutions, reachable with
+/// `get_primitive_handle`. Other resolutions are generated with the builders in this module and
+/// added with `AssetManager::add_primitive`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PrimitiveMesh {
     Quad = 0,
-}
\ No newline at end of file
+    Cube,
+    UvSphere,
+    IcoSphere,
+    Plane,
+    Cylinder,
+    Cone,
+    Capsule,
+    Torus,
+}
+
+impl PrimitiveMesh {
+    pub const ALL: [PrimitiveMesh; 9] = [
+        PrimitiveMesh::Quad,
+        PrimitiveMesh::Cube,
+        PrimitiveMesh::UvSphere,
+        PrimitiveMesh::IcoSphere,
+        PrimitiveMesh::Plane,
+        PrimitiveMesh::Cylinder,
+        PrimitiveMesh::Cone,
+        PrimitiveMesh::Capsule,
+        PrimitiveMesh::Torus,
+    ];
+
+    /// Stable id of the built-in mesh, the same in every run.
+    pub fn asset_id(self) -> AssetId {
+        AssetId::from_path(&format!("primitive://{:?}", self))
+    }
+
+    pub fn generate(self) -> PrimitiveData {
+        match self {
+            PrimitiveMesh::Quad => PrimitiveData {
+                vertices: quad::VERTICES.to_vec(),
+                indices: quad::INDICES.to_vec(),
+            },
+            PrimitiveMesh::Cube => Cube::new().generate(),
+            PrimitiveMesh::UvSphere => UvSphere::new().generate(),
+            PrimitiveMesh::IcoSphere => IcoSphere::new().generate(),
+            PrimitiveMesh::Plane => Plane::new().generate(),
+            PrimitiveMesh::Cylinder => Cylinder::new().generate(),
+            PrimitiveMesh::Cone => Cone::new().generate(),
+            PrimitiveMesh::Capsule => Capsule::new().generate(),
+            PrimitiveMesh::Torus => Torus::new().generate(),
+        }
+    }
+}
+
+/// A mesh generator. Shapes are centred on the origin, fit in a unit cube at their default
+/// sizes and wind counter-clockwise, with `v` running from the top of the texture down.
+pub trait Primitive {
+    fn generate(&self) -> PrimitiveData;
+}
+
+pub struct PrimitiveData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl PrimitiveData {
+    fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Appends a `(columns + 1) x (rows + 1)` grid of vertices, where `vertex` is given the column
+    /// and row of each one. Columns run along `u` and rows along `v`, so `u` cross `v` should point
+    /// into the surface. Triangles collapsed to a line, such as those at a sphere's poles, are skipped.
+    fn grid(&mut self, columns: u32, rows: u32, vertex: impl Fn(u32, u32) -> Vertex) {
+        let base = self.vertices.len() as u32;
+        for row in 0..=rows {
+            for column in 0..=columns {
+                self.vertices.push(vertex(column, row));
+            }
+        }
+
+        let index = |column: u32, row: u32| base + row * (columns + 1) + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = index(column, row);
+                let top_right = index(column + 1, row);
+                let bottom_left = index(column, row + 1);
+                let bottom_right = index(column + 1, row + 1);
+
+                self.triangle([top_left, bottom_left, top_right]);
+                self.triangle([top_right, bottom_left, bottom_right]);
+            }
+        }
+    }
+
+    fn triangle(&mut self, indices: [u32; 3]) {
+        let [a, b, c] = indices.map(|i| glam::Vec3::from(self.vertices[i as usize].position));
+        if (b - a).cross(c - a).length_squared() < 1e-12 {
+            return;
+        }
+
+        self.indices.extend_from_slice(&indices);
+    }
+}
+
+fn vertex(position: glam::Vec3, tex_coords: glam::Vec2, normal: glam::Vec3) -> Vertex {
+    Vertex {
+        position: position.to_array(),
+        tex_coords: tex_coords.to_array(),
+        normal: normal.to_array(),
+    }
+}
+
+/// Point on the unit circle in the XZ plane, `turn` of the way around counter-clockwise seen from above.
+fn around_y(turn: f32) -> glam::Vec3 {
+    let angle = turn * std::f32::consts::TAU;
+    glam::vec3(angle.cos(), 0.0, -angle.sin())
+}
@@ -0,0 +1,53 @@
+use glam::{vec2, vec3, Vec2, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex};
+
+/// A flat grid in the XZ plane facing +Y.
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    size: Vec2,
+    subdivisions: (u32, u32),
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Self {
+            size: Vec2::ONE,
+            subdivisions: (1, 1),
+        }
+    }
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Extent along X and Z.
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Number of quads along X and Z.
+    pub fn with_subdivisions(mut self, x: u32, z: u32) -> Self {
+        self.subdivisions = (x.max(1), z.max(1));
+        self
+    }
+}
+
+impl Primitive for Plane {
+    fn generate(&self) -> PrimitiveData {
+        let (columns, rows) = self.subdivisions;
+
+        let mut data = PrimitiveData::new();
+        data.grid(columns, rows, |column, row| {
+            let tex_coords = vec2(column as f32 / columns as f32, row as f32 / rows as f32);
+            let position = (tex_coords - 0.5) * self.size;
+
+            vertex(vec3(position.x, 0.0, position.y), tex_coords, Vec3::Y)
+        });
+
+        data
+    }
+}
@@ -4,22 +4,26 @@ pub static VERTICES: &[Vertex] = &[
     Vertex {
         position: [-0.5, -0.5, 0.0],
         tex_coords: [0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.5, -0.5, 0.0],
         tex_coords: [1.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.5, 0.5, 0.0],
         tex_coords: [1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
     },
     Vertex {
         position: [-0.5, 0.5, 0.0],
         tex_coords: [0.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
     },
 ];
 
-pub static INDICES: &[u16] = &[
+pub static INDICES: &[u32] = &[
     0, 1, 3,
     1, 2, 3
 ];
\ No newline at end of file
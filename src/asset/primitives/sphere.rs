@@ -0,0 +1,163 @@
+use std::{collections::HashMap, f32::consts::{PI, TAU}};
+
+use glam::{vec2, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex, around_y};
+
+/// A sphere made of rings of latitude, with the texture wrapped around it equirectangularly.
+#[derive(Clone, Copy, Debug)]
+pub struct UvSphere {
+    radius: f32,
+    sectors: u32,
+    stacks: u32,
+}
+
+impl Default for UvSphere {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            sectors: 32,
+            stacks: 16,
+        }
+    }
+}
+
+impl UvSphere {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Number of segments around the equator, and from pole to pole.
+    pub fn with_segments(mut self, sectors: u32, stacks: u32) -> Self {
+        self.sectors = sectors.max(3);
+        self.stacks = stacks.max(2);
+        self
+    }
+}
+
+impl Primitive for UvSphere {
+    fn generate(&self) -> PrimitiveData {
+        let mut data = PrimitiveData::new();
+        data.grid(self.sectors, self.stacks, |column, row| {
+            let tex_coords = vec2(column as f32 / self.sectors as f32, row as f32 / self.stacks as f32);
+            let normal = latitude(around_y(tex_coords.x), tex_coords.y * PI);
+
+            vertex(normal * self.radius, tex_coords, normal)
+        });
+
+        data
+    }
+}
+
+/// A subdivided icosahedron, spreading its triangles more evenly than a `UvSphere`. Uses the
+/// same texture mapping, with vertices duplicated along the seam.
+#[derive(Clone, Copy, Debug)]
+pub struct IcoSphere {
+    radius: f32,
+    subdivisions: u32,
+}
+
+impl Default for IcoSphere {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            subdivisions: 2,
+        }
+    }
+}
+
+impl IcoSphere {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Each subdivision splits every triangle into four.
+    pub fn with_subdivisions(mut self, subdivisions: u32) -> Self {
+        self.subdivisions = subdivisions;
+        self
+    }
+}
+
+impl Primitive for IcoSphere {
+    fn generate(&self) -> PrimitiveData {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut positions: Vec<Vec3> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].map(|(x, y, z)| Vec3::new(x, y, z).normalize()).to_vec();
+
+        let mut triangles: Vec<[usize; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..self.subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: usize, b: usize| *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push((positions[a] + positions[b]).normalize());
+                positions.len() - 1
+            });
+
+            triangles = triangles.into_iter()
+                .flat_map(|[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        // Triangles crossing the seam get copies of their vertices with `u` past 1, and the poles
+        // get a copy per triangle lined up with the rest of it.
+        let mut data = PrimitiveData::new();
+        let mut indices = HashMap::new();
+        for triangle in triangles {
+            let is_pole = triangle.map(|i| positions[i].y.abs() > 0.9999);
+            let mut tex_coords = triangle.map(|i| {
+                let normal = positions[i];
+                vec2(
+                    (-normal.z).atan2(normal.x).rem_euclid(TAU) / TAU,
+                    normal.y.clamp(-1.0, 1.0).acos() / PI,
+                )
+            });
+
+            let (min, max) = (0..3).filter(|&corner| !is_pole[corner])
+                .fold((f32::MAX, f32::MIN), |(min, max), corner| (min.min(tex_coords[corner].x), max.max(tex_coords[corner].x)));
+            if max - min > 0.5 {
+                tex_coords.iter_mut().filter(|uv| uv.x < 0.5).for_each(|uv| uv.x += 1.0);
+            }
+
+            if let Some(pole) = is_pole.iter().position(|&is_pole| is_pole) {
+                tex_coords[pole].x = (0..3).filter(|&corner| corner != pole).map(|corner| tex_coords[corner].x).sum::<f32>() / 2.0;
+            }
+
+            let corners = [0, 1, 2].map(|corner| {
+                let (i, uv) = (triangle[corner], tex_coords[corner]);
+                *indices.entry((i, uv.x.to_bits())).or_insert_with(|| {
+                    data.vertices.push(vertex(positions[i] * self.radius, uv, positions[i]));
+                    data.vertices.len() as u32 - 1
+                })
+            });
+            data.triangle(corners);
+        }
+
+        data
+    }
+}
+
+/// Direction `polar` radians down from +Y towards `outward`.
+pub(super) fn latitude(outward: Vec3, polar: f32) -> Vec3 {
+    outward * polar.sin() + Vec3::Y * polar.cos()
+}
@@ -0,0 +1,63 @@
+use std::f32::consts::TAU;
+
+use glam::{vec2, Vec3};
+
+use super::{Primitive, PrimitiveData, vertex, around_y};
+
+/// A ring around the Y axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Torus {
+    radius: f32,
+    tube_radius: f32,
+    sectors: u32,
+    sides: u32,
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self {
+            radius: 0.35,
+            tube_radius: 0.15,
+            sectors: 32,
+            sides: 16,
+        }
+    }
+}
+
+impl Torus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Distance from the centre to the middle of the tube, and the radius of the tube.
+    pub fn with_radii(mut self, radius: f32, tube_radius: f32) -> Self {
+        self.radius = radius;
+        self.tube_radius = tube_radius;
+        self
+    }
+
+    /// Number of segments around the ring, and around the tube.
+    pub fn with_segments(mut self, sectors: u32, sides: u32) -> Self {
+        self.sectors = sectors.max(3);
+        self.sides = sides.max(3);
+        self
+    }
+}
+
+impl Primitive for Torus {
+    fn generate(&self) -> PrimitiveData {
+        let mut data = PrimitiveData::new();
+        data.grid(self.sectors, self.sides, |column, row| {
+            let tex_coords = vec2(column as f32 / self.sectors as f32, row as f32 / self.sides as f32);
+
+            // `v` starts on the outer equator and runs downwards first.
+            let angle = tex_coords.y * TAU;
+            let outward = around_y(tex_coords.x);
+            let normal = outward * angle.cos() - Vec3::Y * angle.sin();
+
+            vertex(outward * self.radius + normal * self.tube_radius, tex_coords, normal)
+        });
+
+        data
+    }
+}
@@ -0,0 +1,116 @@
+use std::{collections::HashMap, io::{Read, Seek, SeekFrom}, path::{Path, PathBuf}};
+
+use anyhow::{bail, Context as _, Result};
+use async_trait::async_trait;
+
+use super::{AssetSource, not_found};
+
+const MAGIC: &[u8; 4] = b"RPAK";
+const VERSION: u32 = 1;
+
+/// Reads assets out of a single packed archive file.
+///
+/// Layout (little-endian): `RPAK`, version `u32`, entry count `u32`, then per entry the path
+/// length `u32`, UTF-8 path, data offset `u64` and data size `u64`, followed by the file data.
+pub struct ArchiveSource {
+    archive_path: PathBuf,
+    entries: HashMap<String, (u64, u64)>,
+}
+
+impl ArchiveSource {
+    pub fn open(archive_path: impl AsRef<Path>) -> Result<Self> {
+        let archive_path = archive_path.as_ref().to_owned();
+        let mut file = std::fs::File::open(&archive_path)
+            .with_context(|| format!("Unable to read file: {}", archive_path.display()))?;
+
+        let entries = Self::read_index(&mut file)
+            .with_context(|| format!("Invalid archive: {}", archive_path.display()))?;
+
+        Ok(Self {
+            archive_path,
+            entries,
+        })
+    }
+
+    /// Packs `files` into the archive format read by `open`.
+    pub fn pack(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let index_size: usize = files.iter().map(|(path, _)| 4 + path.len() + 16).sum();
+        let mut offset = (MAGIC.len() + 8 + index_size) as u64;
+
+        let mut archive = Vec::new();
+        archive.extend_from_slice(MAGIC);
+        archive.extend_from_slice(&VERSION.to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u32).to_le_bytes());
+
+        for (path, bytes) in files {
+            archive.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            archive.extend_from_slice(path.as_bytes());
+            archive.extend_from_slice(&offset.to_le_bytes());
+            archive.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            offset += bytes.len() as u64;
+        }
+
+        for (_, bytes) in files {
+            archive.extend_from_slice(bytes);
+        }
+
+        archive
+    }
+
+    fn read_index(file: &mut impl Read) -> Result<HashMap<String, (u64, u64)>> {
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a reclipse archive");
+        }
+
+        let version = read_u32(file)?;
+        if version != VERSION {
+            bail!("unsupported archive version {}", version);
+        }
+
+        let count = read_u32(file)?;
+        let mut entries = HashMap::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut path = vec![0; read_u32(file)? as usize];
+            file.read_exact(&mut path)?;
+
+            let path = String::from_utf8(path)?;
+            let offset = read_u64(file)?;
+            let size = read_u64(file)?;
+            entries.insert(path, (offset, size));
+        }
+
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl AssetSource for ArchiveSource {
+    async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        let &(offset, size) = self.entries.get(path)
+            .ok_or_else(|| not_found(format!("No file {} in archive {}", path, self.archive_path.display())))?;
+
+        let mut file = std::fs::File::open(&self.archive_path)
+            .with_context(|| format!("Unable to read file: {}", self.archive_path.display()))?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut bytes = vec![0; size as usize];
+        file.read_exact(&mut bytes)
+            .with_context(|| format!("Truncated archive: {}", self.archive_path.display()))?;
+
+        Ok(bytes)
+    }
+}
+
+pub(crate) fn read_u32(file: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0; 4];
+    file.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_u64(file: &mut impl Read) -> Result<u64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
\ No newline at end of file
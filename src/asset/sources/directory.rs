@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use async_trait::async_trait;
+
+use super::AssetSource;
+
+/// Reads assets from files relative to a root directory.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl AssetSource for DirectorySource {
+    async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        let file_path = self.root.join(path);
+        std::fs::read(&file_path)
+            .with_context(|| format!("Unable to read file: {}", file_path.display()))
+    }
+
+    fn local_path(&self, path: &str) -> Option<PathBuf> {
+        Some(self.root.join(path))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{AssetSource, not_found};
+
+/// Serves files compiled into the binary with `include_bytes!`.
+#[derive(Default)]
+pub struct EmbeddedSource {
+    files: HashMap<&'static str, &'static [u8]>,
+}
+
+impl EmbeddedSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, path: &'static str, bytes: &'static [u8]) -> Self {
+        self.files.insert(path, bytes);
+        self
+    }
+}
+
+#[async_trait]
+impl AssetSource for EmbeddedSource {
+    async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        self.files.get(path)
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| not_found(format!("No embedded file: {}", path)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{AssetSource, not_found};
+
+/// Serves files inserted at runtime, mostly useful for tests and generated content.
+#[derive(Default)]
+pub struct MemorySource {
+    files: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path: &str, bytes: Vec<u8>) {
+        self.files.write().unwrap().insert(path.to_owned(), bytes);
+    }
+
+    pub fn remove(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.write().unwrap().remove(path)
+    }
+}
+
+#[async_trait]
+impl AssetSource for MemorySource {
+    async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        self.files.read().unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| not_found(format!("No file in memory source: {}", path)))
+    }
+}
\ No newline at end of file
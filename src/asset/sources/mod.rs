@@ -0,0 +1,91 @@
+pub mod directory;
+pub mod embedded;
+pub mod memory;
+pub mod archive;
+
+use std::{collections::HashMap, io, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+pub use directory::DirectorySource;
+pub use embedded::EmbeddedSource;
+pub use memory::MemorySource;
+pub use archive::ArchiveSource;
+
+/// Somewhere asset bytes can be read from. Reads run on the `AssetLoader`'s worker threads,
+/// so blocking is fine, but they can't rely on an async runtime being present.
+#[async_trait]
+pub trait AssetSource: Send + Sync {
+    /// Missing files should fail with an `io::ErrorKind::NotFound` error, see `not_found`.
+    async fn read(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// The file on disk backing `path`, if any, so it can be watched for changes.
+    fn local_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Error for a file a source doesn't have, which `is_not_found` recognizes.
+pub fn not_found(message: String) -> anyhow::Error {
+    io::Error::new(io::ErrorKind::NotFound, message).into()
+}
+
+/// Whether reading a file failed only because the file doesn't exist.
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+    })
+}
+
+/// Resolves asset paths to sources by their prefix, e.g. `embedded://textures/default_texture.png`
+/// or `pak://models/crate.obj`. Paths without a prefix go to the default source.
+#[derive(Clone)]
+pub struct AssetSources {
+    default: Arc<dyn AssetSource>,
+    sources: HashMap<String, Arc<dyn AssetSource>>,
+}
+
+impl AssetSources {
+    pub fn new(default: Arc<dyn AssetSource>) -> Self {
+        Self {
+            default,
+            sources: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, source: Arc<dyn AssetSource>) {
+        self.sources.insert(name.to_owned(), source);
+    }
+
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        let (source, path) = self.resolve(path)?;
+        source.read(path).await
+    }
+
+    pub fn local_path(&self, path: &str) -> Option<PathBuf> {
+        let (source, path) = self.resolve(path).ok()?;
+        source.local_path(path)
+    }
+
+    fn resolve<'a>(&self, path: &'a str) -> Result<(&Arc<dyn AssetSource>, &'a str)> {
+        match path.split_once("://") {
+            Some((name, source_path)) => {
+                let source = self.sources.get(name)
+                    .ok_or_else(|| anyhow!("Unknown asset source '{}' in {}", name, path))?;
+                Ok((source, source_path))
+            },
+            None => Ok((&self.default, path)),
+        }
+    }
+}
+
+/// The working directory, plus the `embedded` source holding the engine's built-in assets.
+impl Default for AssetSources {
+    fn default() -> Self {
+        let mut sources = Self::new(Arc::new(DirectorySource::new(".")));
+        sources.insert("embedded", Arc::new(EmbeddedSource::new()
+            .with("textures/default_texture.png", include_bytes!("../../../res/textures/default_texture.png"))));
+        sources
+    }
+}
\ No newline at end of file
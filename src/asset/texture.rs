@@ -1,12 +1,109 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{bail, Context as _, Result};
 use async_trait::async_trait;
 use image::GenericImageView;
+use serde::{Deserialize, Serialize};
 
-use crate::engine::{renderer::Renderer, context::Context};
+use crate::{engine::{renderer::{self, Renderer}, context::Context}, util::cast_slice};
 
-use super::Asset;
+use super::{Asset, loaders::{compressed::CompressedImage, dds, gltf, ktx2}, sources::AssetSources};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// Color textures, decoded to linear when sampled.
+    #[default]
+    Srgb,
+    /// Data textures such as normal or roughness maps, sampled as stored.
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    #[default]
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl From<WrapMode> for wgpu::AddressMode {
+    fn from(wrap: WrapMode) -> Self {
+        match wrap {
+            WrapMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            WrapMode::Repeat => wgpu::AddressMode::Repeat,
+            WrapMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl From<FilterMode> for wgpu::FilterMode {
+    fn from(filter: FilterMode) -> Self {
+        match filter {
+            FilterMode::Nearest => wgpu::FilterMode::Nearest,
+            FilterMode::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// Import settings for textures, read from the manifest or a `.meta` sidecar:
+///
+/// ```yaml
+/// color_space: linear
+/// wrap: repeat
+/// min_filter: nearest
+/// anisotropy: 8
+/// max_size: 1024
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextureSettings {
+    pub color_space: ColorSpace,
+    pub wrap: WrapMode,
+    /// Wrap mode along v, `None` uses `wrap` for both axes.
+    pub wrap_v: Option<WrapMode>,
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    /// Filter between mip levels, unused without mipmaps.
+    pub mipmap_filter: FilterMode,
+    /// Generates a full mip chain. Compressed textures always use the mip levels stored in the file.
+    pub mipmaps: bool,
+    /// Maximum anisotropic filtering samples, 1 disables it. Clamped to 16, and forces all
+    /// filters to linear when enabled.
+    pub anisotropy: u16,
+    /// Downscales textures whose width or height exceeds this, 0 keeps the full size.
+    /// Compressed textures drop their largest mip levels instead.
+    pub max_size: u32,
+    /// Loads HDR images as `Rgba32Float` instead of `Rgba16Float`. These can't be filtered
+    /// and are bound with `Renderer::get_unfilterable_texture_layout`.
+    pub full_precision: bool,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            color_space: ColorSpace::Srgb,
+            wrap: WrapMode::ClampToEdge,
+            wrap_v: None,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            mipmaps: true,
+            anisotropy: 1,
+            max_size: 0,
+            full_precision: false,
+        }
+    }
+}
 
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -21,36 +118,101 @@ impl Texture {
     pub fn from_bytes(
         context: &Context,
         bytes: &[u8],
-        is_normal_map: bool,
+        settings: &TextureSettings,
     ) -> Result<Self> {
         let img = image::load_from_memory(bytes)?;
-        Self::from_image(context, &img, is_normal_map)
+        Self::from_image(context, &img, settings)
     }
 
     pub fn from_image(
         context: &Context,
         img: &image::DynamicImage,
-        is_normal_map: bool,
+        settings: &TextureSettings,
     ) -> Result<Self> {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        Self::from_compressed(context, &Self::encode_image(img, settings), settings)
+    }
 
-        let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
+    /// Converts `img` to the format it's uploaded in and generates its mip levels.
+    /// HDR and OpenEXR images become floating point textures.
+    fn encode_image(img: &image::DynamicImage, settings: &TextureSettings) -> CompressedImage {
+        let img: &image::DynamicImage = &Self::limit_size(img, settings.max_size);
+
+        let (format, levels) = if let image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) = img {
+            let (format, encode): (_, fn(&image::Rgba32FImage) -> Vec<u8>) = if settings.full_precision {
+                (wgpu::TextureFormat::Rgba32Float, |level| cast_slice(level.as_raw()).to_vec())
+            } else {
+                (wgpu::TextureFormat::Rgba16Float, Self::encode_half)
+            };
 
-        let format = if is_normal_map {
-            wgpu::TextureFormat::Rgba8Unorm
+            (format, Self::mip_chain(img.to_rgba32f(), settings.mipmaps, encode))
         } else {
-            wgpu::TextureFormat::Rgba8UnormSrgb
+            let format = match settings.color_space {
+                ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+                ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+            };
+
+            (format, Self::mip_chain(img.to_rgba8(), settings.mipmaps, |level| level.to_vec()))
+        };
+
+        let (width, height) = img.dimensions();
+        CompressedImage {
+            format,
+            width,
+            height,
+            levels,
+        }
+    }
+
+    /// Downscales `img` to fit in `max_size`, keeping its aspect ratio.
+    fn limit_size(img: &image::DynamicImage, max_size: u32) -> std::borrow::Cow<'_, image::DynamicImage> {
+        if max_size == 0 || (img.width() <= max_size && img.height() <= max_size) {
+            return std::borrow::Cow::Borrowed(img);
+        }
+
+        std::borrow::Cow::Owned(img.resize(max_size, max_size, image::imageops::FilterType::Triangle))
+    }
+
+    /// Uploads pre-encoded mip levels, decompressing them first if the device can't
+    /// sample `image.format`.
+    pub fn from_compressed(
+        context: &Context,
+        image: &CompressedImage,
+        settings: &TextureSettings,
+    ) -> Result<Self> {
+        let image = &image.limit_size(settings.max_size);
+
+        if !context.supports_format(image.format) {
+            log::info!("{:?} is not supported by this device, decompressing on the CPU", image.format);
+            let image = image.decompress()?;
+            return Ok(Self::from_levels(context, image.format, image.width, image.height, &image.levels, settings));
+        }
+
+        let (block_width, block_height) = image.format.block_dimensions();
+        if !image.width.is_multiple_of(block_width) || !image.height.is_multiple_of(block_height) {
+            bail!("{}x{} is not a multiple of the {:?} block size", image.width, image.height, image.format);
+        }
+
+        Ok(Self::from_levels(context, image.format, image.width, image.height, &image.levels, settings))
+    }
+
+    fn from_levels(
+        context: &Context,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        levels: &[Vec<u8>],
+        settings: &TextureSettings,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
         };
 
         let texture = context.device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size,
-            mip_level_count: 1,
+            mip_level_count: levels.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
@@ -58,36 +220,35 @@ impl Texture {
             view_formats: &[],
         });
 
-        context.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
+        let block_size = format.block_size(None).unwrap_or(4);
+        for (mip_level, data) in levels.iter().enumerate() {
+            let mip_level = mip_level as u32;
+            let (blocks_x, blocks_y) = CompressedImage::level_blocks(format, width, height, mip_level);
+
+            context.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(blocks_x * block_size),
+                    rows_per_image: Some(blocks_y),
+                },
+                size.mip_level_size(mip_level, wgpu::TextureDimension::D2).physical_size(format),
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = context.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let filterable = renderer::is_filterable(format);
+        let sampler = context.device.create_sampler(&Self::sampler_descriptor(settings, levels.len() > 1, filterable));
 
         let bind_group = context.device.create_bind_group(
             &wgpu::BindGroupDescriptor {
-                layout: &Renderer::get_texture_layout(),
+                layout: &Renderer::get_texture_layout_for(format),
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
@@ -102,12 +263,74 @@ impl Texture {
             }
         );
 
-        Ok(Self {
+        Self {
             texture,
             view,
             sampler,
             bind_group: Some(bind_group),
-        })
+        }
+    }
+
+    /// Encodes `level` as `Rgba16Float` texels.
+    pub(crate) fn encode_half(level: &image::Rgba32FImage) -> Vec<u8> {
+        let halves: Vec<u16> = level.iter().map(|&v| half::f16::from_f32(v).to_bits()).collect();
+        cast_slice(&halves).to_vec()
+    }
+
+    /// Encodes `img` followed by its downsampled mips if `mipmaps` is set, down to 1x1.
+    pub(crate) fn mip_chain<P: image::Pixel + 'static>(
+        img: image::ImageBuffer<P, Vec<P::Subpixel>>,
+        mipmaps: bool,
+        encode: impl Fn(&image::ImageBuffer<P, Vec<P::Subpixel>>) -> Vec<u8>,
+    ) -> Vec<Vec<u8>> {
+        let mut level = img;
+        let mut levels = vec![encode(&level)];
+        while mipmaps && (level.width() > 1 || level.height() > 1) {
+            level = Self::downsample(&level);
+            levels.push(encode(&level));
+        }
+
+        levels
+    }
+
+    /// Halves each dimension (down to 1) with a box-like triangle filter.
+    fn downsample<P: image::Pixel + 'static>(level: &image::ImageBuffer<P, Vec<P::Subpixel>>) -> image::ImageBuffer<P, Vec<P::Subpixel>> {
+        let width = (level.width() / 2).max(1);
+        let height = (level.height() / 2).max(1);
+
+        image::imageops::resize(level, width, height, image::imageops::FilterType::Triangle)
+    }
+
+    fn sampler_descriptor(settings: &TextureSettings, mipmapped: bool, filterable: bool) -> wgpu::SamplerDescriptor<'static> {
+        let address_mode = settings.wrap.into();
+        let address_mode_v = settings.wrap_v.unwrap_or(settings.wrap).into();
+        if !filterable {
+            return wgpu::SamplerDescriptor {
+                address_mode_u: address_mode,
+                address_mode_v,
+                address_mode_w: address_mode,
+                ..Default::default()
+            };
+        }
+
+        // Anisotropic filtering requires every filter to be linear, the mipmap filter included
+        // even when there is only one level.
+        let anisotropy_clamp = settings.anisotropy.clamp(1, 16);
+        let filter = |filter: FilterMode| match anisotropy_clamp {
+            1 => filter.into(),
+            _ => wgpu::FilterMode::Linear,
+        };
+
+        wgpu::SamplerDescriptor {
+            address_mode_u: address_mode,
+            address_mode_v,
+            address_mode_w: address_mode,
+            mag_filter: filter(settings.mag_filter),
+            min_filter: filter(settings.min_filter),
+            mipmap_filter: filter(if mipmapped { settings.mipmap_filter } else { FilterMode::Nearest }),
+            anisotropy_clamp,
+            ..Default::default()
+        }
     }
 
     pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, label: &str) -> Self {
@@ -149,11 +372,61 @@ impl Texture {
     }
 }
 
+/// Compressed formats are uploaded as stored, everything else is decoded with `image`.
+fn is_compressed(file_path: &str) -> bool {
+    let extension = Path::new(file_path).extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    matches!(extension.as_deref(), Some("ktx2" | "dds"))
+}
+
 #[async_trait]
 impl Asset for Texture {
-    async fn load(context: &Context, file_path: &str) -> Arc<Self> {
-        let bytes = tokio::fs::read(file_path).await
-            .expect(&format!("Unable to read file: {}", file_path));
-        Arc::new(Texture::from_bytes(&context, &bytes, false).unwrap())
+    const TYPE_NAME: &'static str = "texture";
+    type Settings = TextureSettings;
+
+    async fn load(context: &Context, sources: &AssetSources, file_path: &str, settings: &TextureSettings) -> Result<Arc<Self>> {
+        if let (gltf_path, Some(label)) = super::split_label(file_path) {
+            return Ok(Arc::new(gltf::load_texture(context, sources, gltf_path, label, settings).await?));
+        }
+
+        let bytes = sources.read(file_path).await?;
+        let extension = Path::new(file_path).extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        let texture = match extension.as_deref() {
+            Some("ktx2") => Texture::from_compressed(context, &ktx2::parse(&bytes, file_path)?, settings),
+            Some("dds") => Texture::from_compressed(context, &dds::parse(&bytes, file_path, settings.color_space == ColorSpace::Srgb)?, settings),
+            _ => Texture::from_bytes(context, &bytes, settings),
+        }.with_context(|| format!("Unable to decode texture: {}", file_path))?;
+
+        Ok(Arc::new(texture))
+    }
+
+    fn cookable(file_path: &str) -> bool {
+        super::split_label(file_path).1.is_none() && !is_compressed(file_path)
+    }
+
+    fn cook(file_path: &str, bytes: &[u8], settings: &TextureSettings) -> Result<Vec<u8>> {
+        let img = image::load_from_memory(bytes)
+            .with_context(|| format!("Unable to decode texture: {}", file_path))?;
+
+        Self::encode_image(&img, settings).to_bytes()
+    }
+
+    fn from_cooked(context: &Context, cooked: &[u8], settings: &TextureSettings) -> Result<Arc<Self>> {
+        let image = CompressedImage::from_bytes(cooked)?;
+        Ok(Arc::new(Texture::from_compressed(context, &image, settings)?))
     }
-}
\ No newline at end of file
+
+    fn gpu_size(&self) -> u64 {
+        let texture = &self.texture;
+        let size: usize = (0..texture.mip_level_count())
+            .map(|level| CompressedImage::level_size(texture.format(), texture.width(), texture.height(), level))
+            .sum();
+
+        size as u64 * texture.depth_or_array_layers() as u64
+    }
+}
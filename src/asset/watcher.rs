@@ -0,0 +1,103 @@
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, sync::{Mutex, mpsc::{self, Receiver}}};
+
+use anyhow::Result;
+use notify::{Watcher, RecommendedWatcher, RecursiveMode, EventKind};
+
+/// Watches the directories of loaded asset files and reports which asset paths changed.
+///
+/// Directories are watched instead of the files themselves since many editors save by
+/// replacing the file, which would silently drop a watch on the old one.
+pub struct AssetWatcher {
+    watcher: RecommendedWatcher,
+    /// In a `Mutex` since `Receiver` isn't `Sync`, which the `AssetManager` resource must be.
+    events: Mutex<Receiver<notify::Result<notify::Event>>>,
+
+    /// Asset paths reported for each file. Several assets share a file when they're labeled
+    /// sub-assets of it.
+    files: HashMap<PathBuf, Vec<String>>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl AssetWatcher {
+    pub fn new() -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+
+        Ok(Self {
+            watcher,
+            events: Mutex::new(rx),
+
+            files: HashMap::new(),
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Watches `local_path`, reporting changes to it as `file_path`. The file doesn't have to
+    /// exist yet as long as its directory does, and is reported once it's created.
+    pub fn watch(&mut self, file_path: &str, local_path: &Path) {
+        let path = local_path.canonicalize().or_else(|e| match (local_path.parent(), local_path.file_name()) {
+            (Some(dir), Some(name)) => dir.canonicalize().map(|dir| dir.join(name)),
+            _ => Err(e),
+        });
+        let path = match path {
+            Ok(path) => path,
+            Err(e) => {
+                log::warn!("Unable to watch {}: {}", file_path, e);
+                return;
+            }
+        };
+
+        if let Some(dir) = path.parent() {
+            if !self.dirs.contains(dir) {
+                match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    Ok(()) => { self.dirs.insert(dir.to_owned()); },
+                    Err(e) => log::warn!("Unable to watch {}: {}", dir.display(), e),
+                }
+            }
+        }
+
+        let file_paths = self.files.entry(path).or_default();
+        if !file_paths.iter().any(|path| path == file_path) {
+            file_paths.push(file_path.to_owned());
+        }
+    }
+
+    pub fn unwatch(&mut self, file_path: &str) {
+        self.files.retain(|_, file_paths| {
+            file_paths.retain(|path| path != file_path);
+            !file_paths.is_empty()
+        });
+    }
+
+    /// Drains pending file system events, returning each changed asset path once.
+    pub fn changed_paths(&self) -> Vec<String> {
+        let mut changed = Vec::new();
+
+        let events = self.events.lock().unwrap();
+        while let Ok(event) = events.try_recv() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("Asset watcher error: {}", e);
+                    continue;
+                }
+            };
+
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in event.paths {
+                for file_path in self.files.get(&path).into_iter().flatten() {
+                    if !changed.contains(file_path) {
+                        changed.push(file_path.clone());
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+}
\ No newline at end of file
@@ -4,13 +4,30 @@ use crate::asset;
 
 #[derive(Component)]
 pub struct Mesh {
-    handle: Handle<asset::Mesh>
+    handle: Handle<asset::Mesh>,
+    /// Level of detail picked last frame.
+    lod: usize,
 }
 
 impl Mesh {
     pub fn new(handle: Handle<asset::Mesh>) -> Self {
         Self {
-            handle
+            handle,
+            lod: 0,
         }
     }
+
+    pub fn handle(&self) -> &Handle<asset::Mesh> {
+        &self.handle
+    }
+
+    pub fn lod(&self) -> usize {
+        self.lod
+    }
+
+    /// Picks the level of detail for `screen_size`, see `asset::Mesh::select_lod`.
+    pub fn update_lod(&mut self, mesh: &asset::Mesh, screen_size: f32) -> usize {
+        self.lod = mesh.select_lod(screen_size, self.lod);
+        self.lod
+    }
 }
\ No newline at end of file
@@ -10,7 +10,7 @@ use bevy_ecs::prelude::*;
 pub struct Transform {
     position: glam::Vec3,
     rotation: glam::Quat,
-    scale: f32,
+    scale: glam::Vec3,
 
     matrix: glam::Mat4,
 }
@@ -18,7 +18,8 @@ pub struct Transform {
 impl Transform {
     pub fn new(position: glam::Vec3, rotation: glam::Vec3, scale: f32) -> Self {
         let rotation = glam::Quat::from_euler(glam::EulerRot::XYZ, rotation.x.to_radians(), rotation.y.to_radians(), rotation.z.to_radians());
-        let matrix = glam::Mat4::from_scale_rotation_translation(glam::Vec3::splat(scale), rotation, position);
+        let scale = glam::Vec3::splat(scale);
+        let matrix = glam::Mat4::from_scale_rotation_translation(scale, rotation, position);
         
         Self {
             position,
@@ -28,6 +29,19 @@ impl Transform {
         }
     }
 
+    /// Builds a transform from an affine matrix, keeping its per-axis scale so the matrix
+    /// rebuilds the same after the position or rotation changes.
+    pub fn from_matrix(matrix: glam::Mat4) -> Self {
+        let (scale, rotation, position) = matrix.to_scale_rotation_translation();
+
+        Self {
+            position,
+            rotation,
+            scale,
+            matrix,
+        }
+    }
+
     pub fn set_position(&mut self, position: glam::Vec3) {
         self.position = position;
         self.dirty = true;
@@ -36,15 +50,19 @@ impl Transform {
     pub fn get_position(&self) -> glam::Vec3 {
         self.position
     }
+
+    pub fn get_matrix(&self) -> glam::Mat4 {
+        self.matrix
+    }
 }
 
 impl Default for Transform {
     fn default() -> Self {
         let position = glam::Vec3::ZERO;
         let rotation = glam::Quat::IDENTITY;
-        let scale = 1.0;
+        let scale = glam::Vec3::ONE;
 
-        let matrix = glam::Mat4::from_scale_rotation_translation(glam::Vec3::splat(scale), rotation, position);
+        let matrix = glam::Mat4::from_scale_rotation_translation(scale, rotation, position);
 
         Self {
             position,
@@ -0,0 +1,120 @@
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl Aabb {
+    /// Returns a zero-sized box at the origin if `points` is empty.
+    pub fn from_points(points: impl IntoIterator<Item = glam::Vec3>) -> Self {
+        let (min, max) = points.into_iter()
+            .fold((glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN)), |(min, max), point| (min.min(point), max.max(point)));
+
+        if min.cmpgt(max).any() {
+            return Self {
+                min: glam::Vec3::ZERO,
+                max: glam::Vec3::ZERO,
+            };
+        }
+
+        Self {
+            min,
+            max,
+        }
+    }
+
+    pub fn center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> glam::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// The box enclosing this one after `matrix` is applied.
+    pub fn transform(&self, matrix: &glam::Mat4) -> Self {
+        let center = matrix.transform_point3(self.center());
+        let half_extents = self.half_extents();
+        let extents = matrix.x_axis.truncate().abs() * half_extents.x
+            + matrix.y_axis.truncate().abs() * half_extents.y
+            + matrix.z_axis.truncate().abs() * half_extents.z;
+
+        Self {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: glam::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Sphere around the centre of `aabb` reaching the farthest of `points`, which is tighter
+    /// than the box's own bounding sphere for most meshes.
+    pub fn from_points(aabb: &Aabb, points: impl IntoIterator<Item = glam::Vec3>) -> Self {
+        let center = aabb.center();
+        let radius = points.into_iter()
+            .map(|point| point.distance_squared(center))
+            .fold(0.0, f32::max)
+            .sqrt();
+
+        Self {
+            center,
+            radius,
+        }
+    }
+
+    /// The sphere enclosing this one after `matrix` is applied, scaled by its largest axis scale.
+    pub fn transform(&self, matrix: &glam::Mat4) -> Self {
+        let scale = matrix.x_axis.truncate().length_squared()
+            .max(matrix.y_axis.truncate().length_squared())
+            .max(matrix.z_axis.truncate().length_squared())
+            .sqrt();
+
+        Self {
+            center: matrix.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// The six planes bounding what a camera can see, facing inwards with `xyz` as the normal and
+/// `w` as the distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [glam::Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view-projection matrix with wgpu's 0 to 1 depth range
+    /// (Gribb & Hartmann).
+    pub fn from_matrix(view_proj: glam::Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z]
+            .map(|plane| plane / plane.truncate().length());
+
+        Self {
+            planes,
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    /// Tests the corner of `aabb` farthest along each plane's normal, so boxes near the
+    /// frustum's corners may be kept even though they're outside.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let corner = glam::Vec3::select(normal.cmpge(glam::Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
@@ -31,9 +31,17 @@ impl Context {
             }
         ).await.unwrap();
 
+        // Enable whichever block compression formats the adapter can sample, compressed
+        // textures in other formats are decompressed on the CPU instead.
+        let features = adapter.features() & (
+            wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+        );
+
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
+                features,
                 limits: if cfg!(target_arch = "wasm32") {
                     wgpu::Limits::downlevel_webgl2_defaults()
                 } else {
@@ -73,6 +81,11 @@ impl Context {
             config,
         })
     }
+
+    /// Whether textures of `format` can be created on this device.
+    pub fn supports_format(&self, format: wgpu::TextureFormat) -> bool {
+        self.device.features().contains(format.required_features())
+    }
 }
 
 pub fn create_render_pipeline(
@@ -1,5 +1,7 @@
 pub mod context;
+pub mod bounds;
 pub mod renderer;
+pub mod skybox;
 pub mod vertex;
 pub mod input;
 pub mod gpu_resource;
\ No newline at end of file
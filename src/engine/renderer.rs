@@ -2,20 +2,37 @@ use std::{sync::{Mutex, Arc}, collections::HashMap};
 
 use once_cell::sync::Lazy;
 
-use crate::{asset::{texture::Texture, asset_manager::{AssetManager, self}, primitives::PrimitiveMesh, handle::Handle}, objects::{entity::DrawEntity, camera::Camera}, scene::Scene, transform::Transform};
+use crate::{asset::{texture::Texture, cube_texture::CubeTexture, mesh::Mesh, asset_manager::{AssetManager, self}, primitives::PrimitiveMesh, handle::Handle}, objects::{entity::DrawEntity, camera::Camera}, scene::Scene, transform::Transform, component};
 
-use super::{vertex::Vertex, context::{Context, Surface}, gpu_resource::GpuResource};
+use super::{bounds::Frustum, skybox::Skybox, vertex::VertexLayout, context::{Context, Surface}, gpu_resource::GpuResource};
 use bevy_ecs::prelude::*;
 
+/// What the last `Renderer::draw` did, for profiling.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub drawn: u32,
+    /// Entities skipped for being outside the camera's frustum.
+    pub culled: u32,
+}
+
 #[derive(Component)]
 pub struct Renderer {
     pub clear_color: wgpu::Color,
     pub texture_view: wgpu::TextureView,
     pub depth_texture: Texture,
-    pub render_pipeline: wgpu::RenderPipeline,
+    pub render_pipeline_layout: wgpu::PipelineLayout,
+    pub color_format: wgpu::TextureFormat,
+    /// One pipeline per mesh vertex layout, built by `prepare_pipeline`.
+    pub pipelines: HashMap<VertexLayout, wgpu::RenderPipeline>,
+    pub skybox: Skybox,
+    /// Cube map drawn behind all geometry, if any.
+    pub skybox_texture: Option<Handle<CubeTexture>>,
+    pub stats: RenderStats,
 }
 
 static TEXTURE_LAYOUT: Lazy<Mutex<Option<Arc<wgpu::BindGroupLayout>>>> = Lazy::new(|| Mutex::new(None));
+static UNFILTERABLE_TEXTURE_LAYOUT: Lazy<Mutex<Option<Arc<wgpu::BindGroupLayout>>>> = Lazy::new(|| Mutex::new(None));
+static CUBE_TEXTURE_LAYOUT: Lazy<Mutex<Option<Arc<wgpu::BindGroupLayout>>>> = Lazy::new(|| Mutex::new(None));
 static CAMERA_LAYOUT: Lazy<Mutex<Option<Arc<wgpu::BindGroupLayout>>>> = Lazy::new(|| Mutex::new(None));
 static TRANSFORM_LAYOUT: Lazy<Mutex<Option<Arc<wgpu::BindGroupLayout>>>> = Lazy::new(|| Mutex::new(None));
 
@@ -52,6 +69,54 @@ impl Renderer {
                 label: Some("texture_bind_group_layout"),
             })));
 
+        let mut unfilterable_texture_layout = UNFILTERABLE_TEXTURE_LAYOUT.lock().unwrap();
+        *unfilterable_texture_layout = Some(Arc::new(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                        count: None,
+                    },
+                ],
+                label: Some("unfilterable_texture_bind_group_layout"),
+            })));
+
+        let mut cube_texture_layout = CUBE_TEXTURE_LAYOUT.lock().unwrap();
+        *cube_texture_layout = Some(Arc::new(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("cube_texture_bind_group_layout"),
+            })));
+        // Released early since the skybox pipeline below reads it back.
+        drop(cube_texture_layout);
+
         let mut camera_layout = CAMERA_LAYOUT.lock().unwrap();
         *camera_layout = Some(Arc::new(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
@@ -97,26 +162,22 @@ impl Renderer {
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = {
-            let shader = wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/basic.wgsl").into()),
-            };
-            create_render_pipeline(
-                &device,
-                &render_pipeline_layout,
-                config.format,
-                Some(wgpu::TextureFormat::Depth32Float),
-                &[Vertex::desc()],
-                shader,
-            )
-        };
+        let mut pipelines = HashMap::new();
+        let default_layout = VertexLayout::default();
+        pipelines.insert(default_layout.clone(), Self::create_pipeline(device, &render_pipeline_layout, config.format, &default_layout));
+
+        let skybox = Skybox::new(device, config.format, Texture::DEPTH_FORMAT);
 
         Self {
             clear_color,
             texture_view,
             depth_texture,
-            render_pipeline,
+            render_pipeline_layout,
+            color_format: config.format,
+            pipelines,
+            skybox,
+            skybox_texture: None,
+            stats: RenderStats::default(),
         }
     }
 
@@ -124,6 +185,24 @@ impl Renderer {
         TEXTURE_LAYOUT.lock().unwrap().as_ref().unwrap().clone()
     }
 
+    /// Layout for textures in formats that can't be filtered, such as `Rgba32Float`.
+    pub fn get_unfilterable_texture_layout() -> Arc<wgpu::BindGroupLayout> {
+        UNFILTERABLE_TEXTURE_LAYOUT.lock().unwrap().as_ref().unwrap().clone()
+    }
+
+    pub fn get_cube_texture_layout() -> Arc<wgpu::BindGroupLayout> {
+        CUBE_TEXTURE_LAYOUT.lock().unwrap().as_ref().unwrap().clone()
+    }
+
+    /// Picks the texture layout matching how `format` can be sampled.
+    pub fn get_texture_layout_for(format: wgpu::TextureFormat) -> Arc<wgpu::BindGroupLayout> {
+        if is_filterable(format) {
+            Self::get_texture_layout()
+        } else {
+            Self::get_unfilterable_texture_layout()
+        }
+    }
+
     pub fn get_camera_layout() -> Arc<wgpu::BindGroupLayout> {
         CAMERA_LAYOUT.lock().unwrap().as_ref().unwrap().clone()
     }
@@ -132,7 +211,47 @@ impl Renderer {
         TRANSFORM_LAYOUT.lock().unwrap().as_ref().unwrap().clone()
     }
 
-    pub fn draw(&mut self, context: &Context, surface: &Surface, camera: &Camera, transform: &GpuResource<Transform>, entity: &Entity, asset_manager: &AssetManager) -> Result<(), wgpu::SurfaceError> {
+    /// Builds the pipeline for meshes with `layout` the first time one is drawn.
+    pub fn prepare_pipeline(&mut self, device: &wgpu::Device, layout: &VertexLayout) {
+        if !self.pipelines.contains_key(layout) {
+            let pipeline = Self::create_pipeline(device, &self.render_pipeline_layout, self.color_format, layout);
+            self.pipelines.insert(layout.clone(), pipeline);
+        }
+    }
+
+    fn create_pipeline(device: &wgpu::Device, pipeline_layout: &wgpu::PipelineLayout, color_format: wgpu::TextureFormat, layout: &VertexLayout) -> wgpu::RenderPipeline {
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/basic.wgsl").into()),
+        };
+
+        create_render_pipeline(
+            device,
+            pipeline_layout,
+            color_format,
+            Some(wgpu::TextureFormat::Depth32Float),
+            &[layout.desc()],
+            shader,
+        )
+    }
+
+    /// Tests the mesh's bounding sphere, then its box for anything the sphere couldn't rule out.
+    pub fn is_visible(frustum: &Frustum, mesh: &Mesh, matrix: &glam::Mat4) -> bool {
+        frustum.intersects_sphere(&mesh.bounding_sphere.transform(matrix))
+            && frustum.intersects_aabb(&mesh.aabb.transform(matrix))
+    }
+
+    /// Updates the level of detail of every mesh entity for its projected size on `camera`.
+    pub fn select_lods(world: &mut World, camera: &Camera, asset_manager: &AssetManager) {
+        let mut query = world.query::<(&component::Transform, &mut component::Mesh)>();
+        for (transform, mut mesh_component) in query.iter_mut(world) {
+            let mesh = asset_manager.get_mesh(mesh_component.handle());
+            let screen_size = camera.screen_size(&mesh.bounding_sphere.transform(&transform.get_matrix()));
+            mesh_component.update_lod(&mesh, screen_size);
+        }
+    }
+
+    pub fn draw(&mut self, context: &Context, surface: &Surface, camera: &Camera, transform: &GpuResource<Transform>, entity: &Entity, world: &World, asset_manager: &AssetManager) -> Result<(), wgpu::SurfaceError> {
         let output = surface.surface.get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -144,7 +263,25 @@ impl Renderer {
         let texture_handle = entity.get_component::<Handle<Texture>>().unwrap();
         let texture = asset_manager.get_texture(texture_handle);
 
-        let mesh = asset_manager.get_primitive_mesh(PrimitiveMesh::Quad);
+        let mesh_component = world.get::<component::Mesh>(*entity).expect("drawn entity has no mesh component");
+        let mesh = asset_manager.get_mesh(mesh_component.handle());
+        let lod = mesh_component.lod();
+        self.prepare_pipeline(&context.device, &mesh.layout);
+
+        self.stats = RenderStats::default();
+        let frustum = camera.frustum();
+        let visible = Self::is_visible(&frustum, &mesh, &transform.data.borrow().get_matrix());
+        if visible {
+            self.stats.drawn += 1;
+        } else {
+            self.stats.culled += 1;
+        }
+
+        let skybox_texture = self.skybox_texture.as_ref()
+            .map(|handle| asset_manager.get_cube_texture(handle));
+        if skybox_texture.is_some() {
+            self.skybox.update(context, camera);
+        }
 
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -172,19 +309,31 @@ impl Renderer {
                 }),
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_pipeline(&self.pipelines[&mesh.layout]);
             render_pass.set_bind_group(0, &camera.bind_group, &[]);
 
-            render_pass.draw_entity(transform, &texture, &mesh);
+            if visible {
+                render_pass.draw_entity(transform, &texture, &mesh, lod);
+            }
+
+            if let Some(skybox_texture) = &skybox_texture {
+                self.skybox.draw(&mut render_pass, skybox_texture);
+            }
         }
     
         context.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+
+        log::trace!("Drew {} entities, culled {}", self.stats.drawn, self.stats.culled);
     
         Ok(())
     }
 }
 
+pub fn is_filterable(format: wgpu::TextureFormat) -> bool {
+    !matches!(format.sample_type(None), Some(wgpu::TextureSampleType::Float { filterable: false }))
+}
+
 pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, extent: &wgpu::Extent3d) -> (wgpu::TextureView, Texture){
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("texture"),
@@ -3,6 +3,7 @@
 pub struct Vertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
+    pub normal: [f32; 3],
 }
 
 impl Vertex {
@@ -20,8 +21,102 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
                 }
             ]
         }
     }
-}
\ No newline at end of file
+}
+/// Per-vertex data a mesh can have on top of the position, UV and normal every `Vertex` has.
+/// Each attribute has a fixed shader location, so shaders can rely on it regardless of which
+/// other attributes the mesh has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    /// Tangent in `xyz` and bitangent sign in `w`.
+    Tangent,
+    Color,
+    TexCoord1,
+    Joints,
+    Weights,
+}
+
+impl VertexAttribute {
+    pub const ALL: [VertexAttribute; 5] = [
+        VertexAttribute::Tangent,
+        VertexAttribute::Color,
+        VertexAttribute::TexCoord1,
+        VertexAttribute::Joints,
+        VertexAttribute::Weights,
+    ];
+
+    pub fn format(self) -> wgpu::VertexFormat {
+        match self {
+            VertexAttribute::Tangent => wgpu::VertexFormat::Float32x4,
+            VertexAttribute::Color => wgpu::VertexFormat::Float32x4,
+            VertexAttribute::TexCoord1 => wgpu::VertexFormat::Float32x2,
+            VertexAttribute::Joints => wgpu::VertexFormat::Uint16x4,
+            VertexAttribute::Weights => wgpu::VertexFormat::Float32x4,
+        }
+    }
+
+    pub fn shader_location(self) -> wgpu::ShaderLocation {
+        match self {
+            VertexAttribute::Tangent => 3,
+            VertexAttribute::Color => 4,
+            VertexAttribute::TexCoord1 => 5,
+            VertexAttribute::Joints => 6,
+            VertexAttribute::Weights => 7,
+        }
+    }
+}
+
+/// Interleaved layout of a mesh's vertex buffer: the `Vertex` fields followed by any extra
+/// attributes in `VertexAttribute::ALL` order. Pipelines are built per layout.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    attributes: Vec<wgpu::VertexAttribute>,
+    array_stride: wgpu::BufferAddress,
+}
+
+impl Default for VertexLayout {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl VertexLayout {
+    pub fn new(extra: &[VertexAttribute]) -> Self {
+        let mut attributes = Vertex::desc().attributes.to_vec();
+        let mut offset = std::mem::size_of::<Vertex>() as wgpu::BufferAddress;
+
+        for attribute in VertexAttribute::ALL.into_iter().filter(|attribute| extra.contains(attribute)) {
+            attributes.push(wgpu::VertexAttribute {
+                offset,
+                shader_location: attribute.shader_location(),
+                format: attribute.format(),
+            });
+            offset += attribute.format().size();
+        }
+
+        Self {
+            attributes,
+            array_stride: offset,
+        }
+    }
+
+    pub fn array_stride(&self) -> wgpu::BufferAddress {
+        self.array_stride
+    }
+
+    pub fn desc(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &self.attributes,
+        }
+    }
+}
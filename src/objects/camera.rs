@@ -4,7 +4,7 @@ use std::f32::consts::FRAC_PI_2;
 
 use instant::Duration;
 
-use crate::util::cast_slice;
+use crate::{engine::bounds::{BoundingSphere, Frustum}, util::cast_slice};
 
 use crate::engine::input::{InputState, Key};
 
@@ -67,6 +67,21 @@ impl Camera {
         self.uniform.update(&self.position, view_proj);
     }
 
+    /// World-space view frustum, for culling.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(self.projection.calc_matrix() * self.calc_matrix())
+    }
+
+    /// Projected height of `sphere` as a fraction of the screen height, above 1 once it fills it.
+    pub fn screen_size(&self, sphere: &BoundingSphere) -> f32 {
+        let distance = sphere.center.distance(self.position);
+        if distance <= sphere.radius {
+            return f32::INFINITY;
+        }
+
+        sphere.radius / (distance * (self.projection.fovy() * 0.5).tan())
+    }
+
     pub fn calc_matrix(&self) -> glam::Mat4 {
         let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
         let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
@@ -106,6 +121,11 @@ impl Projection {
         }
     }
 
+    /// Vertical field of view in radians.
+    pub fn fovy(&self) -> f32 {
+        self.fovy
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         self.aspect = width as f32 / height as f32;
     }
@@ -36,6 +36,10 @@ impl Transform {
     pub fn get_position(&self) -> glam::Vec3 {
         self.position
     }
+
+    pub fn get_matrix(&self) -> glam::Mat4 {
+        self.matrix
+    }
 }
 
 impl Default for Transform {